@@ -61,6 +61,354 @@ pub unsafe fn sfence_vma(asid: usize, addr: usize) {
     }
 }
 
+instruction!(
+    /// `FENCE.TSO` instruction wrapper
+    ///
+    /// Orders all the loads before the fence before all the memory accesses after it, and all the
+    /// stores before the fence before the stores after it, as required by total store ordering.
+    ///
+    /// # Safety
+    ///
+    /// Does not order device input and output: use `fence` for memory-mapped I/O.
+    , fence_tso, "fence.tso");
+// `fence.i`, `pause` and the Svinval and hypervisor fences are emitted with `.insn`, as the
+// assembler only recognizes their mnemonics when the extensions are enabled for the whole crate.
+instruction!(
+    /// `FENCE.I` instruction wrapper (Zifencei)
+    ///
+    /// Synchronizes the instruction and data streams of the current hart: the instructions fetched
+    /// after the fence observe the stores done by the hart before it. Other harts must execute
+    /// their own `FENCE.I` (see `sbi::rfence::remote_fence_i`).
+    ///
+    /// # Safety
+    ///
+    /// Does not synchronize the other harts.
+    , fence_i, ".insn i 0x0F, 1, x0, x0, 0");
+instruction!(
+    /// `PAUSE` instruction wrapper (Zihintpause)
+    ///
+    /// Hints that the current hart is spinning, e.g. waiting for a lock. Executes as a fence with
+    /// no effect on harts without Zihintpause.
+    ///
+    /// # Safety
+    ///
+    /// Has no architectural effect besides the fence on harts without Zihintpause.
+    , pause, ".insn i 0x0F, 0, x0, x0, 0x010");
+instruction!(
+    /// `SFENCE.W.INVAL` instruction wrapper (Svinval)
+    ///
+    /// Orders the stores to the page tables before the fence before the following `SINVAL.VMA`
+    /// instructions.
+    ///
+    /// # Safety
+    ///
+    /// The hart must implement Svinval.
+    , sfence_w_inval, ".insn r 0x73, 0, 0x0C, x0, x0, x0");
+instruction!(
+    /// `SFENCE.INVAL.IR` instruction wrapper (Svinval)
+    ///
+    /// Orders the preceding `SINVAL.VMA` instructions before the implicit references to the page
+    /// tables after the fence.
+    ///
+    /// # Safety
+    ///
+    /// The hart must implement Svinval.
+    , sfence_inval_ir, ".insn r 0x73, 0, 0x0C, x0, x0, x1");
+
+/// Set of memory operations ordered by a `FENCE`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FenceSet(u8);
+
+impl FenceSet {
+    /// No operation
+    pub const NONE: Self = Self(0);
+    /// Device input (`i`)
+    pub const I: Self = Self(0b1000);
+    /// Device output (`o`)
+    pub const O: Self = Self(0b0100);
+    /// Memory reads (`r`)
+    pub const R: Self = Self(0b0010);
+    /// Memory writes (`w`)
+    pub const W: Self = Self(0b0001);
+    /// Memory reads and writes (`rw`)
+    pub const RW: Self = Self(0b0011);
+    /// Device input and output (`io`)
+    pub const IO: Self = Self(0b1100);
+    /// All the operations (`iorw`)
+    pub const IORW: Self = Self(0b1111);
+
+    /// Constructs the set from its encoding in the `pred` and `succ` fields; other bits are
+    /// ignored
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & 0b1111)
+    }
+
+    /// Operations in `self` or `other`
+    #[inline]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Encoding of the set in the `pred` and `succ` fields
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether the set is empty
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl core::ops::BitOr for FenceSet {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+/// Emits the `fence` matching the runtime `pred` and `succ` sets; each set is listed with its
+/// encoding and assembler syntax
+#[cfg(riscv)]
+macro_rules! fence_match {
+    ($pred:expr, $succ:expr, $sets:tt) => {
+        fence_match!(@pred $pred, $succ, $sets, $sets)
+    };
+    (@pred $pred:expr, $succ:expr, [$($bits:literal $set:literal),*], $sets:tt) => {
+        match $pred {
+            $($bits => fence_match!(@succ $succ, $set, $sets),)*
+            _ => {}
+        }
+    };
+    (@succ $succ:expr, $pred:literal, [$($bits:literal $set:literal),*]) => {
+        match $succ {
+            $($bits => core::arch::asm!(concat!("fence ", $pred, ", ", $set), options(nostack)),)*
+            _ => {}
+        }
+    };
+}
+
+/// `FENCE` instruction wrapper
+///
+/// Orders the operations of the `pred` set before the fence with the operations of the `succ` set
+/// after it, as observed by other harts and devices. For instance, `fence(FenceSet::W,
+/// FenceSet::O)` makes the writes to a DMA buffer visible before the write to the register
+/// starting the transfer. The fence has no effect when either set is empty.
+#[inline]
+#[allow(unused_variables)]
+pub fn fence(pred: FenceSet, succ: FenceSet) {
+    match () {
+        #[cfg(riscv)]
+        () => unsafe {
+            fence_match!(
+                pred.0,
+                succ.0,
+                [
+                    0b0001 "w", 0b0010 "r", 0b0011 "rw", 0b0100 "o", 0b0101 "ow", 0b0110 "or",
+                    0b0111 "orw", 0b1000 "i", 0b1001 "iw", 0b1010 "ir", 0b1011 "irw", 0b1100 "io",
+                    0b1101 "iow", 0b1110 "ior", 0b1111 "iorw"
+                ]
+            )
+        },
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// `SINVAL.VMA` instruction wrapper (Svinval)
+///
+/// Invalidates the address translations of `addr` in the address space `asid`, like `SFENCE.VMA`
+/// but without ordering with the stores to the page tables: must be surrounded by `SFENCE.W.INVAL`
+/// and `SFENCE.INVAL.IR`. See `sfence_vma_batch`.
+///
+/// # Safety
+///
+/// The hart must implement Svinval.
+#[inline]
+#[allow(unused_variables)]
+pub unsafe fn sinval_vma(asid: usize, addr: usize) {
+    match () {
+        #[cfg(riscv)]
+        () => core::arch::asm!(".insn r 0x73, 0, 0x0B, x0, {0}, {1}", in(reg) addr, in(reg) asid),
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Invalidates the address translations of several pages of the address space `asid`
+///
+/// With `svinval` (the hart implements Svinval), the invalidations are batched between a single
+/// `SFENCE.W.INVAL` and `SFENCE.INVAL.IR`, so they can proceed in parallel. Otherwise, an
+/// `SFENCE.VMA` is executed for each page.
+///
+/// # Safety
+///
+/// `svinval` must only be set if the hart implements Svinval.
+#[inline]
+pub unsafe fn sfence_vma_batch<I>(svinval: bool, asid: usize, addrs: I)
+where
+    I: IntoIterator<Item = usize>,
+{
+    if svinval {
+        sfence_w_inval();
+        for addr in addrs {
+            sinval_vma(asid, addr);
+        }
+        sfence_inval_ir();
+    } else {
+        for addr in addrs {
+            sfence_vma(asid, addr);
+        }
+    }
+}
+
+/// `HFENCE.VVMA` instruction wrapper (H extension)
+///
+/// Synchronizes updates to the VS-stage page tables of the current guest with the address
+/// translations of guest virtual address `addr` in the guest address space `asid`.
+///
+/// # Safety
+///
+/// The hart must implement the H extension.
+#[inline]
+#[allow(unused_variables)]
+pub unsafe fn hfence_vvma(asid: usize, addr: usize) {
+    match () {
+        #[cfg(riscv)]
+        () => core::arch::asm!(".insn r 0x73, 0, 0x11, x0, {0}, {1}", in(reg) addr, in(reg) asid),
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// `HFENCE.VVMA` instruction wrapper (all guest address spaces and addresses)
+///
+/// # Safety
+///
+/// The hart must implement the H extension.
+#[inline]
+pub unsafe fn hfence_vvma_all() {
+    match () {
+        #[cfg(riscv)]
+        () => core::arch::asm!(".insn r 0x73, 0, 0x11, x0, x0, x0"),
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// `HFENCE.GVMA` instruction wrapper (H extension)
+///
+/// Synchronizes updates to the G-stage page tables with the address translations of guest
+/// physical address `gaddr` for the virtual machine `vmid`.
+///
+/// # Safety
+///
+/// The hart must implement the H extension.
+#[inline]
+#[allow(unused_variables)]
+pub unsafe fn hfence_gvma(vmid: usize, gaddr: usize) {
+    match () {
+        // `rs1` holds the guest physical address shifted right by 2 bits
+        #[cfg(riscv)]
+        () => {
+            core::arch::asm!(".insn r 0x73, 0, 0x31, x0, {0}, {1}", in(reg) gaddr >> 2, in(reg) vmid)
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// `HFENCE.GVMA` instruction wrapper (all virtual machines and guest physical addresses)
+///
+/// # Safety
+///
+/// The hart must implement the H extension.
+#[inline]
+pub unsafe fn hfence_gvma_all() {
+    match () {
+        #[cfg(riscv)]
+        () => core::arch::asm!(".insn r 0x73, 0, 0x31, x0, x0, x0"),
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+macro_rules! cache_block_instruction {
+    ($(#[$attr:meta])*, $fnname:ident, $insn:literal) => (
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// `addr` must point to memory the current hart is allowed to access.
+        #[inline]
+        #[allow(unused_variables)]
+        pub unsafe fn $fnname(addr: usize) {
+            match () {
+                #[cfg(riscv)]
+                () => core::arch::asm!(concat!(".insn i ", $insn), in(reg) addr, options(nostack)),
+
+                #[cfg(not(riscv))]
+                () => unimplemented!(),
+            }
+        }
+    )
+}
+
+// The Zicbom, Zicboz and Zicbop instructions are emitted with `.insn`, as the assembler only
+// recognizes their mnemonics when the extensions are enabled for the whole crate.
+cache_block_instruction!(
+    /// `CBO.CLEAN` instruction wrapper (Zicbom)
+    ///
+    /// Writes the cache block containing `addr` back to memory if it is dirty, and keeps it in the
+    /// caches.
+    , cbo_clean, "0x0F, 2, x0, {0}, 1");
+cache_block_instruction!(
+    /// `CBO.FLUSH` instruction wrapper (Zicbom)
+    ///
+    /// Writes the cache block containing `addr` back to memory if it is dirty, and invalidates it.
+    , cbo_flush, "0x0F, 2, x0, {0}, 2");
+cache_block_instruction!(
+    /// `CBO.INVAL` instruction wrapper (Zicbom)
+    ///
+    /// Invalidates the cache block containing `addr`, discarding its dirty data. Depending on
+    /// `menvcfg.CBIE` (`senvcfg.CBIE`), the instruction may perform a flush instead in S-mode and
+    /// U-mode.
+    , cbo_inval, "0x0F, 2, x0, {0}, 0");
+cache_block_instruction!(
+    /// `CBO.ZERO` instruction wrapper (Zicboz)
+    ///
+    /// Writes zeros to the whole cache block containing `addr`.
+    , cbo_zero, "0x0F, 2, x0, {0}, 4");
+cache_block_instruction!(
+    /// `PREFETCH.I` instruction wrapper (Zicbop)
+    ///
+    /// Hints that the cache block containing `addr` is likely to be fetched as instructions soon.
+    /// Executes as a no-operation on harts without Zicbop.
+    , prefetch_i, "0x13, 6, x0, {0}, 0");
+cache_block_instruction!(
+    /// `PREFETCH.R` instruction wrapper (Zicbop)
+    ///
+    /// Hints that the cache block containing `addr` is likely to be read soon. Executes as a
+    /// no-operation on harts without Zicbop.
+    , prefetch_r, "0x13, 6, x0, {0}, 1");
+cache_block_instruction!(
+    /// `PREFETCH.W` instruction wrapper (Zicbop)
+    ///
+    /// Hints that the cache block containing `addr` is likely to be written soon. Executes as a
+    /// no-operation on harts without Zicbop.
+    , prefetch_w, "0x13, 6, x0, {0}, 3");
+
 /// Blocks the program for *at least* `cycles` CPU cycles.
 ///
 /// This is implemented in assembly so its execution time is independent of the optimization
@@ -90,3 +438,35 @@ pub unsafe fn delay(cycles: u32) {
         () => unimplemented!(),
     }
 }
+
+/// `VSETVL` instruction wrapper
+///
+/// Sets `vtype` and requests `avl` elements, returning the number of elements `vl` the following
+/// vector instructions will process. `vill` is set in `vtype` if the data type is not supported.
+/// See the `vsetvli!` and `vsetivli!` macros for data types known at compile time.
+///
+/// # Safety
+///
+/// Changes `vl` and `vtype`, which vector code generated by the compiler may rely on.
+#[cfg(target_feature = "v")]
+#[inline]
+#[allow(unused_variables)]
+pub unsafe fn vsetvl(avl: usize, vtype: crate::register::vtype::Vtype) -> usize {
+    match () {
+        #[cfg(riscv)]
+        () => {
+            let vl: usize;
+            core::arch::asm!(
+                "vsetvl {0}, {1}, {2}",
+                out(reg) vl,
+                in(reg) avl,
+                in(reg) vtype.bits(),
+                options(nomem, nostack),
+            );
+            vl
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
@@ -0,0 +1,273 @@
+//! Cache-block management (Zicbom, Zicboz and Zicbop)
+//!
+//! The range operations apply the cache-block instructions of `asm` to every block overlapping a
+//! buffer. The size of the cache blocks is not discoverable by software: it is given by the
+//! platform, usually in the `riscv,cbom-block-size` and `riscv,cboz-block-size` properties of the
+//! CPU nodes of the devicetree (see `BlockSize::from_dt_property`).
+//!
+//! `DmaBuffer` keeps a buffer coherent with a device whose DMA accesses bypass the caches.
+//!
+//! In S-mode and U-mode, the instructions must be enabled by the `CBIE`, `CBCFE` and `CBZE` fields
+//! of `menvcfg` (and `senvcfg` for U-mode), or they raise an illegal instruction exception.
+
+use crate::asm;
+
+/// Name of the devicetree property giving the block size of the Zicbom instructions
+pub const DT_CBOM_BLOCK_SIZE: &str = "riscv,cbom-block-size";
+/// Name of the devicetree property giving the block size of the Zicboz instructions
+pub const DT_CBOZ_BLOCK_SIZE: &str = "riscv,cboz-block-size";
+
+/// Size of a cache block in bytes, a power of two
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlockSize(usize);
+
+impl BlockSize {
+    /// Wraps a block size, or returns `None` if `bytes` is not a power of two
+    #[inline]
+    pub const fn new(bytes: usize) -> Option<Self> {
+        if bytes.is_power_of_two() {
+            Some(Self(bytes))
+        } else {
+            None
+        }
+    }
+
+    /// Parses the value of a devicetree block size property, a single big-endian 32-bit cell
+    #[inline]
+    pub fn from_dt_property(value: &[u8]) -> Option<Self> {
+        let cell: [u8; 4] = value.try_into().ok()?;
+        Self::new(u32::from_be_bytes(cell) as usize)
+    }
+
+    /// Size in bytes
+    #[inline]
+    pub const fn bytes(self) -> usize {
+        self.0
+    }
+
+    /// Whether `addr` is at the start of a block
+    #[inline]
+    pub const fn is_aligned(self, addr: usize) -> bool {
+        addr & (self.0 - 1) == 0
+    }
+
+    /// Addresses of the blocks overlapping `len` bytes at `addr`
+    #[inline]
+    fn blocks(self, addr: usize, len: usize) -> impl Iterator<Item = usize> {
+        let start = addr & !(self.0 - 1);
+        let end = if len == 0 { start } else { addr + len };
+        (start..end).step_by(self.0)
+    }
+}
+
+/// Writes the dirty cache blocks overlapping `len` bytes at `addr` back to memory
+///
+/// # Safety
+///
+/// The blocks must be accessible by the current hart.
+#[inline]
+pub unsafe fn clean_range(addr: *const u8, len: usize, block: BlockSize) {
+    for b in block.blocks(addr as usize, len) {
+        asm::cbo_clean(b);
+    }
+}
+
+/// Writes the dirty cache blocks overlapping `len` bytes at `addr` back to memory and invalidates
+/// them
+///
+/// # Safety
+///
+/// The blocks must be accessible by the current hart.
+#[inline]
+pub unsafe fn flush_range(addr: *const u8, len: usize, block: BlockSize) {
+    for b in block.blocks(addr as usize, len) {
+        asm::cbo_flush(b);
+    }
+}
+
+/// Invalidates the cache blocks overlapping `len` bytes at `addr`, discarding their dirty data
+///
+/// # Safety
+///
+/// The blocks must be accessible by the current hart. Whole blocks are invalidated: the writes not
+/// yet written back to memory are lost, including those to bytes outside the range sharing a block
+/// with it.
+#[inline]
+pub unsafe fn invalidate_range(addr: *mut u8, len: usize, block: BlockSize) {
+    for b in block.blocks(addr as usize, len) {
+        asm::cbo_inval(b);
+    }
+}
+
+/// Zeroes `len` bytes at `addr`, with `cbo.zero` for the whole blocks of the range
+///
+/// The bytes of the range sharing a block with bytes outside of it are zeroed with regular
+/// stores.
+///
+/// # Safety
+///
+/// The range must be valid for writes.
+#[inline]
+pub unsafe fn zero_range(addr: *mut u8, len: usize, block: BlockSize) {
+    let start = addr as usize;
+    let end = start + len;
+    let first = (start + block.bytes() - 1) & !(block.bytes() - 1);
+    let last = end & !(block.bytes() - 1);
+    if first >= last {
+        core::ptr::write_bytes(addr, 0, len);
+        return;
+    }
+    core::ptr::write_bytes(addr, 0, first - start);
+    for b in block.blocks(first, last - first) {
+        asm::cbo_zero(b);
+    }
+    core::ptr::write_bytes(last as *mut u8, 0, end - last);
+}
+
+/// Issues a `prefetch.r` hint for every block overlapping `len` bytes at `addr`
+#[inline]
+pub fn prefetch_read_range(addr: *const u8, len: usize, block: BlockSize) {
+    for b in block.blocks(addr as usize, len) {
+        // Safety: prefetches never fault
+        unsafe { asm::prefetch_r(b) };
+    }
+}
+
+/// Issues a `prefetch.w` hint for every block overlapping `len` bytes at `addr`
+#[inline]
+pub fn prefetch_write_range(addr: *const u8, len: usize, block: BlockSize) {
+    for b in block.blocks(addr as usize, len) {
+        // Safety: prefetches never fault
+        unsafe { asm::prefetch_w(b) };
+    }
+}
+
+/// Orders the cache-block operations with the memory and device accesses around them
+#[inline]
+fn fence() {
+    match () {
+        #[cfg(riscv)]
+        () => unsafe { core::arch::asm!("fence iorw, iorw", options(nostack)) },
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Buffer shared with a device whose DMA accesses are not coherent with the caches
+///
+/// The buffer must start and end on cache-block boundaries, so that no other data shares its
+/// blocks: invalidating them would discard the writes to that data. A typical transfer is:
+///
+/// - device reads (transmit): write the data, `before_device_read`, start the DMA;
+/// - device writes (receive): `before_device_write`, start the DMA, wait for its completion,
+///   `after_device_write`, read the data.
+pub struct DmaBuffer<'a> {
+    buf: &'a mut [u8],
+    block: BlockSize,
+}
+
+impl<'a> DmaBuffer<'a> {
+    /// Wraps `buf`, or returns `None` if its start or end is not aligned on a cache block
+    #[inline]
+    pub fn new(buf: &'a mut [u8], block: BlockSize) -> Option<Self> {
+        let start = buf.as_ptr() as usize;
+        if block.is_aligned(start) && block.is_aligned(start + buf.len()) {
+            Some(Self { buf, block })
+        } else {
+            None
+        }
+    }
+
+    /// Address of the buffer, to program the device with
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.buf.as_ptr()
+    }
+
+    /// Length of the buffer in bytes
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether the buffer is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Contents of the buffer
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.buf
+    }
+
+    /// Contents of the buffer, mutable
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.buf
+    }
+
+    /// Writes the contents of the buffer back to memory, for the device to read them
+    #[inline]
+    pub fn before_device_read(&mut self) {
+        // Safety: the buffer is valid and owns its cache blocks
+        unsafe { clean_range(self.buf.as_ptr(), self.buf.len(), self.block) };
+        fence();
+    }
+
+    /// Flushes the buffer from the caches before the device writes to it
+    ///
+    /// Prevents dirty blocks from being evicted over the data written by the device.
+    #[inline]
+    pub fn before_device_write(&mut self) {
+        // Safety: the buffer is valid and owns its cache blocks
+        unsafe { flush_range(self.buf.as_ptr(), self.buf.len(), self.block) };
+        fence();
+    }
+
+    /// Invalidates the buffer from the caches after the device wrote to it, for the hart to read
+    /// the new data
+    #[inline]
+    pub fn after_device_write(&mut self) {
+        fence();
+        // Safety: the buffer is valid and owns its cache blocks, so only its own data is discarded
+        unsafe { invalidate_range(self.buf.as_mut_ptr(), self.buf.len(), self.block) };
+        fence();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockSize;
+
+    #[test]
+    fn block_size() {
+        assert_eq!(BlockSize::new(48), None);
+        assert_eq!(BlockSize::new(0), None);
+        assert_eq!(
+            BlockSize::from_dt_property(&[0, 0, 0, 64]),
+            BlockSize::new(64)
+        );
+        assert_eq!(BlockSize::from_dt_property(&[0, 0, 64]), None);
+        let block = BlockSize::new(64).unwrap();
+        assert!(block.is_aligned(128));
+        assert!(!block.is_aligned(130));
+    }
+
+    #[test]
+    fn blocks() {
+        let block = BlockSize::new(64).unwrap();
+        let mut blocks = [0; 4];
+        let mut n = 0;
+        for b in block.blocks(0x1030, 0x60) {
+            blocks[n] = b;
+            n += 1;
+        }
+        assert_eq!(blocks[..n], [0x1000, 0x1040, 0x1080]);
+        assert_eq!(block.blocks(0x1030, 0).count(), 0);
+        assert_eq!(block.blocks(0x1000, 0x40).count(), 1);
+        assert_eq!(block.blocks(0x103f, 2).count(), 2);
+    }
+}
@@ -0,0 +1,328 @@
+//! Run-time code patching
+//!
+//! `patch` replaces an instruction in memory and synchronizes the instruction caches of all the
+//! harts, so that the new instruction is executed from then on. Each instruction is written with a
+//! single naturally aligned store, so a hart concurrently executing the patched code fetches
+//! either the old or the new instruction, never a mix of both. A 32-bit instruction that is only
+//! 2-byte aligned (with the C extension) is written along with its neighbouring bytes, as a 4-byte
+//! or (on RV64) 8-byte store.
+//!
+//! A 32-bit instruction that no aligned store covers (at an address `≡ 2 (mod 4)` on RV32, or
+//! `≡ 6 (mod 8)` on RV64) is written one 16-bit parcel at a time, synchronizing all the harts after
+//! each step:
+//!
+//! 1. The first parcel is replaced with `c.j 0`, a jump to itself: a hart reaching the instruction
+//!    now waits there, and no hart starts executing the old instruction anymore.
+//! 2. The second parcel is replaced with the one of the new instruction.
+//! 3. The first parcel is replaced with the one of the new instruction, releasing the waiting
+//!    harts.
+//!
+//! After the stores, the current hart executes `FENCE.I`, then the other harts are synchronized as
+//! selected by `RemoteSync`: through the SBI `remote_fence_i` call in S-mode, or by a
+//! platform-specific inter-processor interrupt whose handler executes `asm::fence_i` in M-mode. A
+//! hart waiting on `c.j 0` must be able to take this interrupt.
+//!
+//! The patched code must be writable, and other software must not modify the bytes sharing a
+//! store with the instruction at the same time.
+
+use crate::asm::{self, FenceSet};
+use crate::sbi::{self, HartMask};
+
+/// Instruction to write
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    /// 16-bit compressed instruction
+    Compressed(u16),
+    /// 32-bit instruction
+    Full(u32),
+}
+
+impl Instruction {
+    /// Wraps an instruction, whose length is given by its two lowest bits
+    ///
+    /// The upper 16 bits of `bits` are ignored for compressed instructions.
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        if bits & 0b11 == 0b11 {
+            Self::Full(bits)
+        } else {
+            Self::Compressed(bits as u16)
+        }
+    }
+
+    /// Size of the instruction in bytes
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::Compressed(_) => 2,
+            Self::Full(_) => 4,
+        }
+    }
+
+    /// Encoding of the instruction
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        match self {
+            Self::Compressed(bits) => bits as u32,
+            Self::Full(bits) => bits,
+        }
+    }
+}
+
+/// How the instruction caches of the other harts are synchronized
+pub enum RemoteSync<'a> {
+    /// No other hart executes the patched code (single hart system, or the other harts are
+    /// stopped and execute `FENCE.I` when they resume)
+    Local,
+    /// SBI `remote_fence_i` call to the harts of the mask (S-mode)
+    Sbi(HartMask),
+    /// Function interrupting the other harts and returning once each of them executed
+    /// `asm::fence_i` (M-mode, e.g. with the software interrupts of the ACLINT)
+    Ipi(&'a mut dyn FnMut()),
+}
+
+/// Code patching error
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The instruction is not 2-byte aligned
+    Misaligned,
+    /// The instruction was written, but a `remote_fence_i` call failed: other harts may still
+    /// execute the old instruction, or wait on `c.j 0`
+    Sbi(sbi::Error),
+}
+
+/// Replaces the instruction at `addr` and synchronizes the instruction caches of all the harts
+///
+/// # Safety
+///
+/// `addr` must point to an instruction of the same length as `insn`, in writable memory that is
+/// not concurrently written. The old and new instructions must both be valid for the harts that
+/// may execute them while the patch propagates.
+#[inline]
+pub unsafe fn patch(addr: *mut u8, insn: Instruction, sync: RemoteSync<'_>) -> Result<(), Error> {
+    patch_all(&[(addr, insn)], sync)
+}
+
+/// Replaces several instructions, and synchronizes the instruction caches once for all of them
+/// (after each step if one is written in steps)
+///
+/// Each instruction is replaced as a whole (see the module documentation), but the harts may
+/// execute a mix of patched and unpatched instructions until the function returns.
+///
+/// # Safety
+///
+/// See `patch`. The instructions must not share a store with each other.
+pub unsafe fn patch_all(
+    patches: &[(*mut u8, Instruction)],
+    mut sync: RemoteSync<'_>,
+) -> Result<(), Error> {
+    write_all(patches, || {
+        // Make the stores visible to the instruction fetches of this hart, then of the other harts
+        asm::fence_i();
+        asm::fence(FenceSet::W, FenceSet::IORW);
+        match &mut sync {
+            RemoteSync::Local => Ok(()),
+            RemoteSync::Sbi(hart_mask) => {
+                sbi::rfence::remote_fence_i(*hart_mask).map_err(Error::Sbi)
+            }
+            RemoteSync::Ipi(ipi) => {
+                ipi();
+                Ok(())
+            }
+        }
+    })
+}
+
+/// `c.j 0`, written over the first parcel of an instruction written in several steps
+const JUMP_TO_SELF: u32 = 0xa001;
+
+/// Writes the instructions, calling `sync` after each step, and returns the first error of `sync`
+unsafe fn write_all(
+    patches: &[(*mut u8, Instruction)],
+    mut sync: impl FnMut() -> Result<(), Error>,
+) -> Result<(), Error> {
+    // Check all the instructions first, so that none is written if one is misaligned
+    if patches.iter().any(|&(addr, _)| addr as usize % 2 != 0) {
+        return Err(Error::Misaligned);
+    }
+    // The instructions that no store covers, written one parcel at a time
+    let split = || {
+        patches
+            .iter()
+            .filter(|&&(addr, insn)| Store::new(addr as usize, insn.size()).is_none())
+            .map(|&(addr, insn)| (addr as usize, insn.bits()))
+    };
+
+    for &(addr, insn) in patches {
+        match Store::new(addr as usize, insn.size()) {
+            Some(store) => store.write(insn.bits(), insn.size()),
+            None => write_parcel(addr as usize, JUMP_TO_SELF),
+        }
+    }
+    let mut result = sync();
+    if split().next().is_none() {
+        return result;
+    }
+
+    for (addr, bits) in split() {
+        write_parcel(addr + 2, bits >> 16);
+    }
+    result = result.and(sync());
+    for (addr, bits) in split() {
+        write_parcel(addr, bits);
+    }
+    result.and(sync())
+}
+
+/// Writes the 16 lowest bits of `bits` at the 2-byte aligned `addr`
+#[inline]
+unsafe fn write_parcel(addr: usize, bits: u32) {
+    Store {
+        addr,
+        size: 2,
+        offset: 0,
+    }
+    .write(bits, 2);
+}
+
+/// Naturally aligned store covering an instruction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Store {
+    /// Address of the store
+    addr: usize,
+    /// Size of the store in bytes
+    size: usize,
+    /// Offset of the instruction in the stored bytes
+    offset: usize,
+}
+
+impl Store {
+    /// Smallest store covering the `len` bytes at `addr`, if any
+    fn new(addr: usize, len: usize) -> Option<Self> {
+        if addr % 2 != 0 {
+            return None;
+        }
+        let mut size = len;
+        while size <= core::mem::size_of::<usize>() {
+            let aligned = addr & !(size - 1);
+            if addr + len <= aligned + size {
+                return Some(Self {
+                    addr: aligned,
+                    size,
+                    offset: addr - aligned,
+                });
+            }
+            size *= 2;
+        }
+        None
+    }
+
+    /// Replaces the bits of `old` covered by the instruction with `insn` (little-endian)
+    fn merge(&self, old: u64, insn: u32, len: usize) -> u64 {
+        let shift = self.offset * 8;
+        let mask = (u64::MAX >> (64 - len * 8)) << shift;
+        (old & !mask) | ((insn as u64) << shift & mask)
+    }
+
+    /// Writes the `len` bytes of `insn` with a single store, keeping the other bytes of the store
+    /// unchanged
+    unsafe fn write(&self, insn: u32, len: usize) {
+        match self.size {
+            2 => core::ptr::write_volatile(self.addr as *mut u16, insn as u16),
+            4 => {
+                let ptr = self.addr as *mut u32;
+                let old = core::ptr::read_volatile(ptr) as u64;
+                core::ptr::write_volatile(ptr, self.merge(old, insn, len) as u32);
+            }
+            #[cfg(target_pointer_width = "64")]
+            8 => {
+                let ptr = self.addr as *mut u64;
+                let old = core::ptr::read_volatile(ptr);
+                core::ptr::write_volatile(ptr, self.merge(old, insn, len));
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_all, Error, Instruction, Store};
+
+    #[test]
+    fn instruction() {
+        // c.nop and addi x0, x0, 0
+        assert_eq!(
+            Instruction::from_bits(0x0001),
+            Instruction::Compressed(0x0001)
+        );
+        assert_eq!(Instruction::from_bits(0x0000_0013), Instruction::Full(0x13));
+        assert_eq!(Instruction::from_bits(0xffff_0001).bits(), 0x0001);
+        assert_eq!(Instruction::Full(0x13).size(), 4);
+    }
+
+    #[test]
+    fn store() {
+        let store = |addr, len| Store::new(addr, len).map(|s| (s.addr, s.size, s.offset));
+        assert_eq!(store(0x1001, 2), None);
+        assert_eq!(store(0x1002, 2), Some((0x1002, 2, 0)));
+        assert_eq!(store(0x1004, 4), Some((0x1004, 4, 0)));
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(store(0x1002, 4), Some((0x1000, 8, 2)));
+            assert_eq!(store(0x1006, 4), None);
+        }
+    }
+
+    #[test]
+    fn split() {
+        // c.nop at the start, and lui a0, 0x12345 crossing the end of the largest store
+        let mut code = [0x1111_1111_1111_1111u64; 2];
+        let base = code.as_mut_ptr() as usize;
+        let crossing = core::mem::size_of::<usize>() - 2;
+        let patches = [
+            (base as *mut u8, Instruction::Compressed(0x0001)),
+            ((base + crossing) as *mut u8, Instruction::Full(0x1234_5537)),
+        ];
+        let mut steps = [[0u16; 8]; 3];
+        let mut step = 0;
+        let result = unsafe {
+            write_all(&patches, || {
+                steps[step] = core::ptr::read(base as *const [u16; 8]);
+                step += 1;
+                Ok(())
+            })
+        };
+        assert_eq!(result, Ok(()));
+        assert_eq!(step, 3);
+        let parcel = crossing / 2;
+        assert_eq!(steps[0][0], 0x0001);
+        assert_eq!(steps[0][parcel..parcel + 2], [0xa001, 0x1111]);
+        assert_eq!(steps[1][parcel..parcel + 2], [0xa001, 0x1234]);
+        assert_eq!(steps[2][parcel..parcel + 2], [0x5537, 0x1234]);
+
+        // Odd address
+        let patches = [((base + 1) as *mut u8, Instruction::Compressed(0x0001))];
+        let result = unsafe { write_all(&patches, || unreachable!()) };
+        assert_eq!(result, Err(Error::Misaligned));
+    }
+
+    #[test]
+    fn write() {
+        let mut code = [0x1111_1111u32, 0x2222_2222];
+        let base = code.as_mut_ptr() as usize;
+        unsafe {
+            Store::new(base + 2, 2).unwrap().write(0x0001, 2);
+            Store::new(base + 4, 4).unwrap().write(0x0000_0013, 4);
+        }
+        assert_eq!(code, [0x0001_1111, 0x0000_0013]);
+
+        #[cfg(target_pointer_width = "64")]
+        {
+            let mut code = [0x1111_1111_1111_1111u64];
+            let base = code.as_mut_ptr() as usize;
+            unsafe { Store::new(base + 2, 4).unwrap().write(0x1234_5673, 4) };
+            assert_eq!(code, [0x1111_1234_5673_1111]);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Floating-point register state
+//!
+//! `FpState` holds the 32 floating-point registers and `fcsr`. On targets with the D extension the
+//! registers are saved as 64-bit values; with the F extension only, the upper half of each slot is
+//! unused.
+//!
+//! The registers are restored by `_riscv_fp_restore`, an assembly routine to call from the trap
+//! return code: compiled code may use the floating-point registers at any time, so a restored
+//! state only reaches the interrupted context if nothing runs between the restore and `mret` (or
+//! `sret`). The routine takes the address of an `FpState` in `a0`, is called with `call` (it
+//! returns to `ra`), and only modifies `t0`, `f0`-`f31`, `fcsr` and the `FS` field:
+//!
+//! ```text
+//! la a0, TASK_FP_STATE
+//! call _riscv_fp_restore
+//! # restore the integer registers
+//! mret
+//! ```
+//!
+//! Nothing is restored when the `FS` field of `mstatus` (`sstatus` with the `s-mode` feature) is
+//! `Off`; otherwise `FS` is set to `Clean`.
+
+use super::{set_clean, state, FS_SHIFT};
+use crate::register::mstatus::FS;
+
+/// Complete floating-point register state
+///
+/// The layout is used by `_riscv_fp_restore`: `f` at offset 0 and `fcsr` at offset 256.
+#[repr(C)]
+#[derive(Clone, Debug)]
+pub struct FpState {
+    /// Registers `f0`-`f31`
+    pub f: [u64; 32],
+    /// Floating-point control and status register
+    pub fcsr: u32,
+}
+
+impl FpState {
+    /// Constructs a zeroed state, equal to the `Initial` state of the floating-point unit
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            f: [0; 32],
+            fcsr: 0,
+        }
+    }
+
+    /// Saves the floating-point registers if they are `Dirty`, and returns whether they were saved
+    ///
+    /// Nothing is saved when the `FS` field is `Off`, `Initial` or `Clean`: the registers are
+    /// either disabled or unchanged since they were last saved or restored. After a save, `FS` is
+    /// set to `Clean`.
+    ///
+    /// # Safety
+    ///
+    /// Changes the `FS` field of `mstatus` (`sstatus` with the `s-mode` feature).
+    #[inline]
+    pub unsafe fn save(&mut self) -> bool {
+        if state(FS_SHIFT) != FS::Dirty {
+            return false;
+        }
+        save(self);
+        set_clean(FS_SHIFT);
+        true
+    }
+}
+
+impl Default for FpState {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accesses the 32 floating-point registers at the address in `$base` with instruction `$op`
+#[cfg(all(riscv, target_feature = "f"))]
+#[rustfmt::skip]
+macro_rules! fp_regs {
+    ($op:expr, $base:literal) => {
+        concat!(
+            $op, " f0, 0(", $base, ")\n",
+            $op, " f1, 8(", $base, ")\n",
+            $op, " f2, 16(", $base, ")\n",
+            $op, " f3, 24(", $base, ")\n",
+            $op, " f4, 32(", $base, ")\n",
+            $op, " f5, 40(", $base, ")\n",
+            $op, " f6, 48(", $base, ")\n",
+            $op, " f7, 56(", $base, ")\n",
+            $op, " f8, 64(", $base, ")\n",
+            $op, " f9, 72(", $base, ")\n",
+            $op, " f10, 80(", $base, ")\n",
+            $op, " f11, 88(", $base, ")\n",
+            $op, " f12, 96(", $base, ")\n",
+            $op, " f13, 104(", $base, ")\n",
+            $op, " f14, 112(", $base, ")\n",
+            $op, " f15, 120(", $base, ")\n",
+            $op, " f16, 128(", $base, ")\n",
+            $op, " f17, 136(", $base, ")\n",
+            $op, " f18, 144(", $base, ")\n",
+            $op, " f19, 152(", $base, ")\n",
+            $op, " f20, 160(", $base, ")\n",
+            $op, " f21, 168(", $base, ")\n",
+            $op, " f22, 176(", $base, ")\n",
+            $op, " f23, 184(", $base, ")\n",
+            $op, " f24, 192(", $base, ")\n",
+            $op, " f25, 200(", $base, ")\n",
+            $op, " f26, 208(", $base, ")\n",
+            $op, " f27, 216(", $base, ")\n",
+            $op, " f28, 224(", $base, ")\n",
+            $op, " f29, 232(", $base, ")\n",
+            $op, " f30, 240(", $base, ")\n",
+            $op, " f31, 248(", $base, ")\n",
+        )
+    };
+}
+
+#[cfg(all(riscv, target_feature = "d"))]
+macro_rules! fp_store {
+    () => {
+        "fsd"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "d"))]
+macro_rules! fp_load {
+    () => {
+        "fld"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", not(target_feature = "d")))]
+macro_rules! fp_store {
+    () => {
+        "fsw"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", not(target_feature = "d")))]
+macro_rules! fp_load {
+    () => {
+        "flw"
+    };
+}
+
+#[inline]
+#[allow(unused_variables)]
+unsafe fn save(state: &mut FpState) {
+    match () {
+        #[cfg(all(riscv, target_feature = "f"))]
+        () => {
+            let fcsr: usize;
+            core::arch::asm!(
+                fp_regs!(fp_store!(), "{0}"),
+                "frcsr {1}",
+                in(reg) state.f.as_mut_ptr(),
+                out(reg) fcsr,
+                options(nostack),
+            );
+            state.fcsr = fcsr as u32;
+        }
+
+        #[cfg(not(all(riscv, target_feature = "f")))]
+        () => unimplemented!(),
+    }
+}
+
+#[cfg(all(riscv, target_feature = "f", not(feature = "s-mode")))]
+macro_rules! status {
+    () => {
+        "mstatus"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", feature = "s-mode"))]
+macro_rules! status {
+    () => {
+        "sstatus"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f"))]
+core::arch::global_asm!(concat!(
+    ".section .text._riscv_fp_restore\n",
+    ".global _riscv_fp_restore\n",
+    ".align 2\n",
+    "_riscv_fp_restore:\n",
+    "csrr t0, ",
+    status!(),
+    "\n",
+    "srli t0, t0, 13\n",
+    "andi t0, t0, 3\n",
+    // FS = Off
+    "beqz t0, 1f\n",
+    fp_regs!(fp_load!(), "a0"),
+    "lw t0, 256(a0)\n",
+    "fscsr t0\n",
+    // FS = Clean
+    "li t0, 1 << 13\n",
+    "csrc ",
+    status!(),
+    ", t0\n",
+    "li t0, 1 << 14\n",
+    "csrs ",
+    status!(),
+    ", t0\n",
+    "1:\n",
+    "ret\n",
+));
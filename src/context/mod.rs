@@ -0,0 +1,584 @@
+//! Execution contexts and context switching
+//!
+//! A `Context` holds the registers a function call must preserve: `ra`, `sp`, `s0`-`s11`, `tp`
+//! when the `hart-local-scratch` feature is enabled, and `gp` when the `context-gp` feature is
+//! enabled. `switch` saves the current registers into one context and resumes another one; the
+//! caller-saved registers are preserved by the compiler around the call, like for any other
+//! function.
+//!
+//! Without `hart-local-scratch`, `tp` holds the ID of the hart (see [`hart`](crate::hart)) and is
+//! left untouched, so that a context resumed on another hart reads the ID of that hart.
+//!
+//! On targets with the F extension, the callee-saved floating-point registers (`fs0`-`fs11`) and
+//! `fcsr` are saved lazily, driven by the `FS` field of `mstatus` (`sstatus` with the `s-mode`
+//! feature):
+//!
+//! - `Off`: the floating-point unit is disabled, nothing is saved or restored.
+//! - `Dirty`: the registers were written since they were last saved or restored, and are saved.
+//! - `Initial` or `Clean`: the registers still hold the last saved or restored state, and saving
+//!   them again is skipped.
+//!
+//! The registers of the resumed context are then restored if it has saved any, and `FS` is set to
+//! `Clean`.
+//!
+//! `FpState` and `VectorState` hold the complete floating-point and vector register sets, e.g. to
+//! preempt a task from a trap handler, and use the same lazy saving scheme with the `FS` and `VS`
+//! fields. They are saved from Rust, and restored by the assembly routines `_riscv_fp_restore` and
+//! `_riscv_vector_restore`, called by the trap return code right before `mret` or `sret`.
+//!
+//! ```no_run
+//! use riscv::context::{self, Context};
+//!
+//! static mut MAIN: Context = Context::empty();
+//! static mut TASK: Context = Context::empty();
+//! static mut STACK: [u8; 4096] = [0; 4096];
+//!
+//! extern "C" fn task(arg: usize) -> ! {
+//!     loop {
+//!         // back to `main`
+//!         unsafe {
+//!             context::switch(
+//!                 core::ptr::addr_of_mut!(TASK),
+//!                 core::ptr::addr_of!(MAIN),
+//!             )
+//!         };
+//!     }
+//! }
+//!
+//! unsafe {
+//!     let stack_top = core::ptr::addr_of_mut!(STACK).cast::<u8>().add(4096);
+//!     TASK = Context::new(task, 42, stack_top);
+//!     context::switch(core::ptr::addr_of_mut!(MAIN), core::ptr::addr_of!(TASK));
+//! }
+//! ```
+
+pub mod fp;
+pub mod vector;
+
+pub use fp::FpState;
+pub use vector::VectorState;
+
+use crate::register::mstatus::FS;
+
+/// Offset of the `FS` field in `mstatus` and `sstatus`
+const FS_SHIFT: usize = 13;
+/// Offset of the `VS` field in `mstatus` and `sstatus`
+const VS_SHIFT: usize = 9;
+
+/// Reads `mstatus` (`sstatus` with the `s-mode` feature)
+#[inline]
+fn read_status() -> usize {
+    match () {
+        #[cfg(all(riscv, not(feature = "s-mode")))]
+        () => {
+            let r: usize;
+            unsafe { core::arch::asm!("csrr {0}, mstatus", out(reg) r, options(nomem, nostack)) };
+            r
+        }
+        #[cfg(all(riscv, feature = "s-mode"))]
+        () => {
+            let r: usize;
+            unsafe { core::arch::asm!("csrr {0}, sstatus", out(reg) r, options(nomem, nostack)) };
+            r
+        }
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Reads the 2-bit extension state field at `shift` of `mstatus` (`sstatus` with `s-mode`)
+#[inline]
+fn state(shift: usize) -> FS {
+    match (read_status() >> shift) & 0b11 {
+        0b00 => FS::Off,
+        0b01 => FS::Initial,
+        0b10 => FS::Clean,
+        _ => FS::Dirty,
+    }
+}
+
+/// Sets the 2-bit extension state field at `shift` of `mstatus` (`sstatus` with `s-mode`) to
+/// `Clean`
+#[inline]
+unsafe fn set_clean(shift: usize) {
+    match () {
+        #[cfg(all(riscv, not(feature = "s-mode")))]
+        () => {
+            crate::register::mstatus::read_clear(1 << shift);
+            crate::register::mstatus::read_set(2 << shift);
+        }
+        #[cfg(all(riscv, feature = "s-mode"))]
+        () => {
+            crate::register::sstatus::read_clear(1 << shift);
+            crate::register::sstatus::read_set(2 << shift);
+        }
+        #[cfg(not(riscv))]
+        () => {
+            let _ = shift;
+            unimplemented!()
+        }
+    }
+}
+
+/// Saved callee-saved registers of an execution context
+#[repr(C)]
+#[derive(Clone, Debug)]
+pub struct Context {
+    /// Return address: where `switch` resumes the context
+    pub ra: usize,
+    /// Stack pointer
+    pub sp: usize,
+    /// Saved registers `s0`-`s11`
+    pub s: [usize; 12],
+    /// Thread pointer, only saved and restored with the `hart-local-scratch` feature
+    pub tp: usize,
+    /// Global pointer, only saved and restored with the `context-gp` feature
+    pub gp: usize,
+    /// Saved floating-point registers `fs0`-`fs11`
+    #[cfg(target_feature = "f")]
+    pub fs: [u64; 12],
+    /// Floating-point control and status register
+    #[cfg(target_feature = "f")]
+    pub fcsr: u32,
+    /// Whether `fs` and `fcsr` hold a saved state
+    #[cfg(target_feature = "f")]
+    pub fp_saved: bool,
+}
+
+impl Context {
+    /// Constructs a context with all the registers zeroed
+    ///
+    /// Only suitable as the `from` argument of `switch`, which fills it.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            ra: 0,
+            sp: 0,
+            s: [0; 12],
+            tp: 0,
+            gp: 0,
+            #[cfg(target_feature = "f")]
+            fs: [0; 12],
+            #[cfg(target_feature = "f")]
+            fcsr: 0,
+            #[cfg(target_feature = "f")]
+            fp_saved: false,
+        }
+    }
+
+    /// Constructs a context that calls `entry(arg)` on the stack ending at `stack_top` when it is
+    /// first resumed
+    ///
+    /// `tp` and `gp` are copied from the current hart, so that the thread pointer and global
+    /// variables remain accessible. No floating-point state is saved.
+    ///
+    /// # Safety
+    ///
+    /// `stack_top` must be the end of a memory region large enough for the stack of `entry`, not
+    /// used by anything else while the context runs.
+    #[inline]
+    pub unsafe fn new(entry: extern "C" fn(usize) -> !, arg: usize, stack_top: *mut u8) -> Self {
+        let mut context = Self::empty();
+        context.ra = _riscv_context_entry as unsafe extern "C" fn() as usize;
+        // The stack pointer is 16-byte aligned in the standard calling convention
+        context.sp = stack_top as usize & !0xF;
+        context.s[0] = arg;
+        context.s[1] = entry as usize;
+        context.tp = read_tp();
+        context.gp = read_gp();
+        context
+    }
+}
+
+impl Default for Context {
+    #[inline]
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+extern "C" {
+    fn _riscv_context_switch(from: *mut Context, to: *const Context);
+    fn _riscv_context_entry();
+}
+
+/// Saves the current registers into `from` and resumes `to`
+///
+/// Returns when another `switch` resumes `from`.
+///
+/// # Safety
+///
+/// `from` must be valid for writes and `to` must hold a context saved by `switch` or constructed
+/// with `Context::new`, whose stack is still valid. `from` and `to` may be the same context. If
+/// `from` may be resumed on another hart, it must not hold references obtained from
+/// `HartLocal::borrow`.
+#[inline]
+pub unsafe fn switch(from: *mut Context, to: *const Context) {
+    match () {
+        #[cfg(riscv)]
+        () => _riscv_context_switch(from, to),
+
+        #[cfg(not(riscv))]
+        () => {
+            let _ = (from, to);
+            unimplemented!()
+        }
+    }
+}
+
+#[inline]
+fn read_tp() -> usize {
+    match () {
+        #[cfg(riscv)]
+        () => {
+            let r: usize;
+            unsafe {
+                core::arch::asm!("mv {0}, tp", out(reg) r, options(nomem, nostack, preserves_flags))
+            };
+            r
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+#[inline]
+fn read_gp() -> usize {
+    match () {
+        #[cfg(riscv)]
+        () => {
+            let r: usize;
+            unsafe {
+                core::arch::asm!("mv {0}, gp", out(reg) r, options(nomem, nostack, preserves_flags))
+            };
+            r
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+#[cfg(riscv32)]
+macro_rules! regbytes {
+    () => {
+        "4"
+    };
+}
+
+#[cfg(riscv64)]
+macro_rules! regbytes {
+    () => {
+        "8"
+    };
+}
+
+#[cfg(riscv32)]
+macro_rules! store {
+    () => {
+        "sw"
+    };
+}
+
+#[cfg(riscv64)]
+macro_rules! store {
+    () => {
+        "sd"
+    };
+}
+
+#[cfg(riscv32)]
+macro_rules! load {
+    () => {
+        "lw"
+    };
+}
+
+#[cfg(riscv64)]
+macro_rules! load {
+    () => {
+        "ld"
+    };
+}
+
+/// Saves integer register `$reg` into word `$index` of the context in `a0`
+#[cfg(riscv)]
+macro_rules! save {
+    ($reg:literal, $index:literal) => {
+        concat!(
+            store!(),
+            " ",
+            $reg,
+            ", ",
+            $index,
+            "*",
+            regbytes!(),
+            "(a0)\n"
+        )
+    };
+}
+
+/// Restores integer register `$reg` from word `$index` of the context in `a1`
+#[cfg(riscv)]
+macro_rules! restore {
+    ($reg:literal, $index:literal) => {
+        concat!(load!(), " ", $reg, ", ", $index, "*", regbytes!(), "(a1)\n")
+    };
+}
+
+#[cfg(all(riscv, feature = "context-gp"))]
+macro_rules! switch_gp {
+    () => {
+        concat!(save!("gp", 15), restore!("gp", 15))
+    };
+}
+
+#[cfg(all(riscv, not(feature = "context-gp")))]
+macro_rules! switch_gp {
+    () => {
+        ""
+    };
+}
+
+#[cfg(all(riscv, feature = "hart-local-scratch"))]
+macro_rules! switch_tp {
+    () => {
+        concat!(save!("tp", 14), restore!("tp", 14))
+    };
+}
+
+// `tp` holds the hart ID, which must not follow the context to another hart
+#[cfg(all(riscv, not(feature = "hart-local-scratch")))]
+macro_rules! switch_tp {
+    () => {
+        ""
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", not(feature = "s-mode")))]
+macro_rules! status {
+    () => {
+        "mstatus"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", feature = "s-mode"))]
+macro_rules! status {
+    () => {
+        "sstatus"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "d"))]
+macro_rules! fp_store {
+    () => {
+        "fsd"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "d"))]
+macro_rules! fp_load {
+    () => {
+        "fld"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", not(target_feature = "d")))]
+macro_rules! fp_store {
+    () => {
+        "fsw"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "f", not(target_feature = "d")))]
+macro_rules! fp_load {
+    () => {
+        "flw"
+    };
+}
+
+/// Accesses floating-point register `$reg`, slot `$index` of `fs` in the context in `$base`
+#[cfg(all(riscv, target_feature = "f"))]
+macro_rules! fp_access {
+    ($op:expr, $reg:literal, $index:literal, $base:literal) => {
+        concat!(
+            $op,
+            " ",
+            $reg,
+            ", 16*",
+            regbytes!(),
+            "+",
+            $index,
+            "*8(",
+            $base,
+            ")\n"
+        )
+    };
+}
+
+/// Lazily saves the floating-point registers into the context in `a0` and restores them from the
+/// context in `a1`, then marks them clean
+#[cfg(all(riscv, target_feature = "f"))]
+macro_rules! switch_fp {
+    () => {
+        concat!(
+            "csrr t0, ",
+            status!(),
+            "\n",
+            "srli t0, t0, 13\n",
+            "andi t0, t0, 3\n",
+            // FS = Off
+            "beqz t0, 3f\n",
+            "li t1, 3\n",
+            // FS != Dirty: the saved state is up to date
+            "bne t0, t1, 1f\n",
+            fp_access!(fp_store!(), "fs0", 0, "a0"),
+            fp_access!(fp_store!(), "fs1", 1, "a0"),
+            fp_access!(fp_store!(), "fs2", 2, "a0"),
+            fp_access!(fp_store!(), "fs3", 3, "a0"),
+            fp_access!(fp_store!(), "fs4", 4, "a0"),
+            fp_access!(fp_store!(), "fs5", 5, "a0"),
+            fp_access!(fp_store!(), "fs6", 6, "a0"),
+            fp_access!(fp_store!(), "fs7", 7, "a0"),
+            fp_access!(fp_store!(), "fs8", 8, "a0"),
+            fp_access!(fp_store!(), "fs9", 9, "a0"),
+            fp_access!(fp_store!(), "fs10", 10, "a0"),
+            fp_access!(fp_store!(), "fs11", 11, "a0"),
+            "frcsr t1\n",
+            "sw t1, 16*",
+            regbytes!(),
+            "+96(a0)\n",
+            "li t1, 1\n",
+            "sb t1, 16*",
+            regbytes!(),
+            "+100(a0)\n",
+            "1:\n",
+            "lbu t1, 16*",
+            regbytes!(),
+            "+100(a1)\n",
+            "beqz t1, 2f\n",
+            fp_access!(fp_load!(), "fs0", 0, "a1"),
+            fp_access!(fp_load!(), "fs1", 1, "a1"),
+            fp_access!(fp_load!(), "fs2", 2, "a1"),
+            fp_access!(fp_load!(), "fs3", 3, "a1"),
+            fp_access!(fp_load!(), "fs4", 4, "a1"),
+            fp_access!(fp_load!(), "fs5", 5, "a1"),
+            fp_access!(fp_load!(), "fs6", 6, "a1"),
+            fp_access!(fp_load!(), "fs7", 7, "a1"),
+            fp_access!(fp_load!(), "fs8", 8, "a1"),
+            fp_access!(fp_load!(), "fs9", 9, "a1"),
+            fp_access!(fp_load!(), "fs10", 10, "a1"),
+            fp_access!(fp_load!(), "fs11", 11, "a1"),
+            "lw t1, 16*",
+            regbytes!(),
+            "+96(a1)\n",
+            "fscsr t1\n",
+            "2:\n",
+            // FS = Clean
+            "li t1, 1 << 13\n",
+            "csrc ",
+            status!(),
+            ", t1\n",
+            "li t1, 1 << 14\n",
+            "csrs ",
+            status!(),
+            ", t1\n",
+            "3:\n",
+        )
+    };
+}
+
+#[cfg(all(riscv, not(target_feature = "f")))]
+macro_rules! switch_fp {
+    () => {
+        ""
+    };
+}
+
+#[cfg(riscv)]
+core::arch::global_asm!(concat!(
+    ".section .text._riscv_context_switch\n",
+    ".global _riscv_context_switch\n",
+    ".align 2\n",
+    "_riscv_context_switch:\n",
+    save!("ra", 0),
+    save!("sp", 1),
+    save!("s0", 2),
+    save!("s1", 3),
+    save!("s2", 4),
+    save!("s3", 5),
+    save!("s4", 6),
+    save!("s5", 7),
+    save!("s6", 8),
+    save!("s7", 9),
+    save!("s8", 10),
+    save!("s9", 11),
+    save!("s10", 12),
+    save!("s11", 13),
+    restore!("ra", 0),
+    restore!("sp", 1),
+    restore!("s0", 2),
+    restore!("s1", 3),
+    restore!("s2", 4),
+    restore!("s3", 5),
+    restore!("s4", 6),
+    restore!("s5", 7),
+    restore!("s6", 8),
+    restore!("s7", 9),
+    restore!("s8", 10),
+    restore!("s9", 11),
+    restore!("s10", 12),
+    restore!("s11", 13),
+    switch_tp!(),
+    switch_gp!(),
+    switch_fp!(),
+    "ret\n",
+    "\n",
+    ".section .text._riscv_context_entry\n",
+    ".global _riscv_context_entry\n",
+    ".align 2\n",
+    // First resumption of a context built by `Context::new`: call `entry(arg)`
+    "_riscv_context_entry:\n",
+    "mv a0, s0\n",
+    "jr s1\n",
+));
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, FpState, VectorState};
+    use core::mem::size_of;
+    use memoffset::offset_of;
+
+    const REGBYTES: usize = size_of::<usize>();
+
+    // The offsets are hard-coded in `_riscv_context_switch`
+    #[test]
+    fn offset_of() {
+        assert_eq!(0, offset_of!(Context, ra));
+        assert_eq!(REGBYTES, offset_of!(Context, sp));
+        assert_eq!(2 * REGBYTES, offset_of!(Context, s));
+        assert_eq!(14 * REGBYTES, offset_of!(Context, tp));
+        assert_eq!(15 * REGBYTES, offset_of!(Context, gp));
+    }
+
+    // The offsets are hard-coded in `_riscv_fp_restore` and `_riscv_vector_restore`
+    #[test]
+    fn offset_of_state() {
+        assert_eq!(0, offset_of!(FpState, f));
+        assert_eq!(256, offset_of!(FpState, fcsr));
+        assert_eq!(0, offset_of!(VectorState<16>, vl));
+        assert_eq!(REGBYTES, offset_of!(VectorState<16>, vtype));
+        assert_eq!(2 * REGBYTES, offset_of!(VectorState<16>, vstart));
+        assert_eq!(3 * REGBYTES, offset_of!(VectorState<16>, vcsr));
+        assert_eq!(4 * REGBYTES, offset_of!(VectorState<16>, v));
+    }
+
+    #[cfg(target_feature = "f")]
+    #[test]
+    fn offset_of_fp() {
+        assert_eq!(16 * REGBYTES, offset_of!(Context, fs));
+        assert_eq!(16 * REGBYTES + 96, offset_of!(Context, fcsr));
+        assert_eq!(16 * REGBYTES + 100, offset_of!(Context, fp_saved));
+    }
+}
@@ -0,0 +1,241 @@
+//! Vector register state
+//!
+//! `VectorState` holds the 32 vector registers and the `vl`, `vtype`, `vstart` and `vcsr` CSRs.
+//! The size of the vector registers is only known at run time, from `vlenb`: `VLENB` is the
+//! largest `vlenb` the state can hold, and saving the registers of a hart with longer vectors
+//! panics.
+//!
+//! The registers are restored by `_riscv_vector_restore`, an assembly routine to call from the
+//! trap return code, for the same reason as `_riscv_fp_restore` (see [`fp`](super::fp)): a
+//! restored state only reaches the interrupted context if no compiled code runs between the
+//! restore and `mret` (or `sret`). The routine takes the address of a `VectorState` in `a0`, is
+//! called with `call`, and only modifies `t0`, `t1`, `v0`-`v31`, the vector CSRs and the `VS`
+//! field. The state must have been saved on a hart with the same `vlenb`.
+//!
+//! Nothing is restored when the `VS` field of `mstatus` (`sstatus` with the `s-mode` feature) is
+//! `Off`; otherwise `VS` is set to `Clean`.
+
+use super::{set_clean, state, VS_SHIFT};
+use crate::register::mstatus::FS;
+
+/// Complete vector register state, for harts whose `vlenb` is at most `VLENB`
+///
+/// The CSRs come first so that `_riscv_vector_restore` finds them at fixed offsets, whatever
+/// `VLENB`.
+#[repr(C)]
+#[derive(Clone, Debug)]
+pub struct VectorState<const VLENB: usize> {
+    /// Vector length
+    pub vl: usize,
+    /// Vector data type
+    pub vtype: usize,
+    /// Start element index
+    pub vstart: usize,
+    /// Vector control and status register (`vxrm` and `vxsat`)
+    pub vcsr: usize,
+    /// Registers `v0`-`v31`, stored contiguously from the start of the array, `vlenb` bytes each
+    pub v: [[u8; VLENB]; 32],
+}
+
+impl<const VLENB: usize> VectorState<VLENB> {
+    /// Constructs a zeroed state
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            vl: 0,
+            vtype: 0,
+            vstart: 0,
+            vcsr: 0,
+            v: [[0; VLENB]; 32],
+        }
+    }
+
+    /// Saves the vector registers if they are `Dirty`, and returns whether they were saved
+    ///
+    /// Nothing is saved when the `VS` field is `Off`, `Initial` or `Clean`: the registers are
+    /// either disabled or unchanged since they were last saved or restored. After a save, `VS` is
+    /// set to `Clean`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vlenb` is larger than `VLENB`.
+    ///
+    /// # Safety
+    ///
+    /// Changes the `VS` field of `mstatus` (`sstatus` with the `s-mode` feature).
+    #[inline]
+    pub unsafe fn save(&mut self) -> bool {
+        if state(VS_SHIFT) != FS::Dirty {
+            return false;
+        }
+        assert!(read_vlenb() <= VLENB);
+        save(self);
+        set_clean(VS_SHIFT);
+        true
+    }
+}
+
+impl<const VLENB: usize> Default for VectorState<VLENB> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+fn read_vlenb() -> usize {
+    match () {
+        #[cfg(all(riscv, target_feature = "v"))]
+        () => crate::register::vlenb::read(),
+
+        #[cfg(not(all(riscv, target_feature = "v")))]
+        () => unimplemented!(),
+    }
+}
+
+/// Saves the vector registers and CSRs
+///
+/// Vector register `n` is stored `n * vlenb` bytes after the start of `state.v`, with whole
+/// register stores that do not depend on `vl` and `vtype`. `vstart` is cleared for the stores and
+/// set back afterwards.
+#[inline]
+#[allow(unused_variables)]
+unsafe fn save<const VLENB: usize>(state: &mut VectorState<VLENB>) {
+    match () {
+        #[cfg(all(riscv, target_feature = "v"))]
+        () => {
+            let (vl, vtype, vstart, vcsr): (usize, usize, usize, usize);
+            core::arch::asm!(
+                "csrr {vl}, 0xC20",
+                "csrr {vtype}, 0xC21",
+                "csrr {vstart}, 0x008",
+                "csrr {vcsr}, 0x00F",
+                "csrw 0x008, zero",
+                "csrr {step}, 0xC22",
+                "slli {step}, {step}, 3",
+                "vs8r.v v0, ({v})",
+                "add {v}, {v}, {step}",
+                "vs8r.v v8, ({v})",
+                "add {v}, {v}, {step}",
+                "vs8r.v v16, ({v})",
+                "add {v}, {v}, {step}",
+                "vs8r.v v24, ({v})",
+                "csrw 0x008, {vstart}",
+                v = inout(reg) state.v.as_mut_ptr() => _,
+                step = out(reg) _,
+                vl = out(reg) vl,
+                vtype = out(reg) vtype,
+                vstart = out(reg) vstart,
+                vcsr = out(reg) vcsr,
+                options(nostack),
+            );
+            state.vl = vl;
+            state.vtype = vtype;
+            state.vstart = vstart;
+            state.vcsr = vcsr;
+        }
+
+        #[cfg(not(all(riscv, target_feature = "v")))]
+        () => unimplemented!(),
+    }
+}
+
+#[cfg(all(riscv32, target_feature = "v"))]
+macro_rules! regbytes {
+    () => {
+        "4"
+    };
+}
+
+#[cfg(all(riscv64, target_feature = "v"))]
+macro_rules! regbytes {
+    () => {
+        "8"
+    };
+}
+
+#[cfg(all(riscv32, target_feature = "v"))]
+macro_rules! load {
+    () => {
+        "lw"
+    };
+}
+
+#[cfg(all(riscv64, target_feature = "v"))]
+macro_rules! load {
+    () => {
+        "ld"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "v", not(feature = "s-mode")))]
+macro_rules! status {
+    () => {
+        "mstatus"
+    };
+}
+
+#[cfg(all(riscv, target_feature = "v", feature = "s-mode"))]
+macro_rules! status {
+    () => {
+        "sstatus"
+    };
+}
+
+// Restores the vector registers, then `vl`, `vtype`, `vstart` and `vcsr`
+#[cfg(all(riscv, target_feature = "v"))]
+core::arch::global_asm!(concat!(
+    ".section .text._riscv_vector_restore\n",
+    ".global _riscv_vector_restore\n",
+    ".align 2\n",
+    "_riscv_vector_restore:\n",
+    "csrr t0, ",
+    status!(),
+    "\n",
+    "srli t0, t0, 9\n",
+    "andi t0, t0, 3\n",
+    // VS = Off
+    "beqz t0, 1f\n",
+    "csrw 0x008, zero\n",
+    "csrr t0, 0xC22\n",
+    "slli t0, t0, 3\n",
+    "addi t1, a0, 4*",
+    regbytes!(),
+    "\n",
+    "vl8re8.v v0, (t1)\n",
+    "add t1, t1, t0\n",
+    "vl8re8.v v8, (t1)\n",
+    "add t1, t1, t0\n",
+    "vl8re8.v v16, (t1)\n",
+    "add t1, t1, t0\n",
+    "vl8re8.v v24, (t1)\n",
+    // `vl` is lower than VLMAX for `vtype`, so `vsetvl` sets it back unchanged
+    load!(),
+    " t0, 0(a0)\n",
+    load!(),
+    " t1, 1*",
+    regbytes!(),
+    "(a0)\n",
+    "vsetvl zero, t0, t1\n",
+    load!(),
+    " t0, 2*",
+    regbytes!(),
+    "(a0)\n",
+    "csrw 0x008, t0\n",
+    load!(),
+    " t0, 3*",
+    regbytes!(),
+    "(a0)\n",
+    "csrw 0x00F, t0\n",
+    // VS = Clean
+    "li t0, 1 << 9\n",
+    "csrc ",
+    status!(),
+    ", t0\n",
+    "li t0, 1 << 10\n",
+    "csrs ",
+    status!(),
+    ", t0\n",
+    "1:\n",
+    "ret\n",
+));
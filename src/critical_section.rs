@@ -0,0 +1,27 @@
+//! `critical-section` implementation for single hart systems
+//!
+//! Enabled by the `critical-section-single-hart` feature. Critical sections disable the
+//! interrupts of the current privilege mode, like `interrupt::free`: `mstatus.MIE`, or
+//! `sstatus.SIE` with the `s-mode` feature. This is not sound on multi-hart systems, where the
+//! other harts keep running.
+
+use crate::interrupt;
+use critical_section::{set_impl, Impl, RawRestoreState};
+
+struct SingleHartCriticalSection;
+set_impl!(SingleHartCriticalSection);
+
+unsafe impl Impl for SingleHartCriticalSection {
+    #[inline]
+    unsafe fn acquire() -> RawRestoreState {
+        interrupt::disable_and_save()
+    }
+
+    #[inline]
+    unsafe fn release(was_enabled: RawRestoreState) {
+        // Only re-enable the interrupts if they were enabled before the critical section
+        if was_enabled {
+            interrupt::enable();
+        }
+    }
+}
@@ -0,0 +1,1559 @@
+//! Instruction decoder
+//!
+//! Decodes the instructions of the RV32 and RV64 I, M, A, F, D and C extensions, Zicsr, Zifencei
+//! and the privileged architecture into an `Instruction`, e.g. to emulate the instruction found at
+//! `mepc` (or in `mtinst`) in a trap handler. The decoder is pure Rust and can be used on any
+//! target.
+//!
+//! Compressed instructions are expanded to the 32-bit instruction they are equivalent to (e.g.
+//! `c.lw` decodes as `Load`): `instruction_len` tells how far to advance the program counter.
+//! Reserved encodings, and the encodings of unsupported extensions, decode as `None`.
+//!
+//! ```
+//! use riscv::decode::{self, Instruction, LoadWidth, Reg, Xlen};
+//!
+//! // lw a0, 8(sp)
+//! assert_eq!(
+//!     decode::decode(0x00812503, Xlen::Rv64),
+//!     Some(Instruction::Load {
+//!         width: LoadWidth::W,
+//!         rd: Reg::new(10),
+//!         rs1: Reg::SP,
+//!         offset: 8,
+//!     })
+//! );
+//! ```
+
+use crate::asm::FenceSet;
+use crate::register::fcsr::RoundingMode;
+
+/// Base integer register width, selecting between the RV32 and RV64 encodings
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Xlen {
+    Rv32,
+    Rv64,
+}
+
+impl Xlen {
+    /// Register width of the target
+    #[inline]
+    pub const fn native() -> Self {
+        match () {
+            #[cfg(target_pointer_width = "32")]
+            () => Xlen::Rv32,
+            #[cfg(not(target_pointer_width = "32"))]
+            () => Xlen::Rv64,
+        }
+    }
+}
+
+/// Integer register `x0`-`x31`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Reg(u8);
+
+impl Reg {
+    /// Hard-wired zero (`x0`)
+    pub const ZERO: Self = Self(0);
+    /// Return address (`x1`)
+    pub const RA: Self = Self(1);
+    /// Stack pointer (`x2`)
+    pub const SP: Self = Self(2);
+
+    /// Register `x{index}`; the index is taken modulo 32
+    #[inline]
+    pub const fn new(index: u8) -> Self {
+        Self(index & 0b11111)
+    }
+
+    /// Index of the register
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Floating-point register `f0`-`f31`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FReg(u8);
+
+impl FReg {
+    /// Register `f{index}`; the index is taken modulo 32
+    #[inline]
+    pub const fn new(index: u8) -> Self {
+        Self(index & 0b11111)
+    }
+
+    /// Index of the register
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Condition of a conditional branch
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BranchCondition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+/// Width and signedness of an integer load
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LoadWidth {
+    B,
+    H,
+    W,
+    D,
+    Bu,
+    Hu,
+    Wu,
+}
+
+impl LoadWidth {
+    /// Number of bytes loaded
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            LoadWidth::B | LoadWidth::Bu => 1,
+            LoadWidth::H | LoadWidth::Hu => 2,
+            LoadWidth::W | LoadWidth::Wu => 4,
+            LoadWidth::D => 8,
+        }
+    }
+
+    /// Whether the loaded value is sign-extended
+    #[inline]
+    pub const fn is_signed(self) -> bool {
+        matches!(
+            self,
+            LoadWidth::B | LoadWidth::H | LoadWidth::W | LoadWidth::D
+        )
+    }
+}
+
+/// Width of an integer store
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StoreWidth {
+    B,
+    H,
+    W,
+    D,
+}
+
+impl StoreWidth {
+    /// Number of bytes stored
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            StoreWidth::B => 1,
+            StoreWidth::H => 2,
+            StoreWidth::W => 4,
+            StoreWidth::D => 8,
+        }
+    }
+}
+
+/// Register-immediate operation
+///
+/// The shift amount of the shifts is given in the immediate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OpImm {
+    Addi,
+    Slti,
+    Sltiu,
+    Xori,
+    Ori,
+    Andi,
+    Slli,
+    Srli,
+    Srai,
+    Addiw,
+    Slliw,
+    Srliw,
+    Sraiw,
+}
+
+/// Register-register operation (I and M extensions)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+    Mul,
+    Mulh,
+    Mulhsu,
+    Mulhu,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+    Addw,
+    Subw,
+    Sllw,
+    Srlw,
+    Sraw,
+    Mulw,
+    Divw,
+    Divuw,
+    Remw,
+    Remuw,
+}
+
+/// Operation of a CSR instruction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CsrOp {
+    /// `csrrw` and `csrrwi`
+    ReadWrite,
+    /// `csrrs` and `csrrsi`
+    ReadSet,
+    /// `csrrc` and `csrrci`
+    ReadClear,
+}
+
+/// Source operand of a CSR instruction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CsrSource {
+    /// Register
+    Reg(Reg),
+    /// 5-bit unsigned immediate
+    Imm(u8),
+}
+
+/// Width of an atomic memory operation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AmoWidth {
+    W,
+    D,
+}
+
+/// Atomic memory operation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AmoOp {
+    Swap,
+    Add,
+    Xor,
+    And,
+    Or,
+    Min,
+    Max,
+    Minu,
+    Maxu,
+}
+
+/// Floating-point format
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Precision {
+    /// Single precision (F extension)
+    Single,
+    /// Double precision (D extension)
+    Double,
+}
+
+impl Precision {
+    /// Number of bytes of a value
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            Precision::Single => 4,
+            Precision::Double => 8,
+        }
+    }
+}
+
+/// Rounding mode of a floating-point instruction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    /// Rounding mode encoded in the instruction
+    Static(RoundingMode),
+    /// Rounding mode of `frm`
+    Dynamic,
+}
+
+/// Fused multiply-add operation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FmaOp {
+    /// `rs1 * rs2 + rs3`
+    Madd,
+    /// `rs1 * rs2 - rs3`
+    Msub,
+    /// `-(rs1 * rs2) + rs3`
+    Nmsub,
+    /// `-(rs1 * rs2) - rs3`
+    Nmadd,
+}
+
+/// Floating-point arithmetic operation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FpArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Floating-point sign injection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FpSignOp {
+    SgnJ,
+    SgnJn,
+    SgnJx,
+}
+
+/// Floating-point comparison
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FpCompareOp {
+    Eq,
+    Lt,
+    Le,
+}
+
+/// Integer format of a floating-point conversion
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IntFormat {
+    /// Signed 32-bit
+    W,
+    /// Unsigned 32-bit
+    Wu,
+    /// Signed 64-bit
+    L,
+    /// Unsigned 64-bit
+    Lu,
+}
+
+/// Decoded instruction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    /// `lui`, `imm` holds the upper 20 bits
+    Lui {
+        rd: Reg,
+        imm: i32,
+    },
+    /// `auipc`, `imm` holds the upper 20 bits
+    Auipc {
+        rd: Reg,
+        imm: i32,
+    },
+    Jal {
+        rd: Reg,
+        offset: i32,
+    },
+    Jalr {
+        rd: Reg,
+        rs1: Reg,
+        offset: i32,
+    },
+    Branch {
+        cond: BranchCondition,
+        rs1: Reg,
+        rs2: Reg,
+        offset: i32,
+    },
+    Load {
+        width: LoadWidth,
+        rd: Reg,
+        rs1: Reg,
+        offset: i32,
+    },
+    Store {
+        width: StoreWidth,
+        rs1: Reg,
+        rs2: Reg,
+        offset: i32,
+    },
+    OpImm {
+        op: OpImm,
+        rd: Reg,
+        rs1: Reg,
+        imm: i32,
+    },
+    Op {
+        op: Op,
+        rd: Reg,
+        rs1: Reg,
+        rs2: Reg,
+    },
+    /// `fence`, `fence.tso` (`fm` = `0b1000`) and `pause`
+    Fence {
+        fm: u8,
+        pred: FenceSet,
+        succ: FenceSet,
+    },
+    FenceI,
+    Ecall,
+    Ebreak,
+    Csr {
+        op: CsrOp,
+        rd: Reg,
+        csr: u16,
+        src: CsrSource,
+    },
+    LoadReserved {
+        width: AmoWidth,
+        rd: Reg,
+        rs1: Reg,
+        aq: bool,
+        rl: bool,
+    },
+    StoreConditional {
+        width: AmoWidth,
+        rd: Reg,
+        rs1: Reg,
+        rs2: Reg,
+        aq: bool,
+        rl: bool,
+    },
+    Amo {
+        op: AmoOp,
+        width: AmoWidth,
+        rd: Reg,
+        rs1: Reg,
+        rs2: Reg,
+        aq: bool,
+        rl: bool,
+    },
+    FpLoad {
+        precision: Precision,
+        rd: FReg,
+        rs1: Reg,
+        offset: i32,
+    },
+    FpStore {
+        precision: Precision,
+        rs1: Reg,
+        rs2: FReg,
+        offset: i32,
+    },
+    FpFma {
+        op: FmaOp,
+        precision: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rs2: FReg,
+        rs3: FReg,
+        rm: Rounding,
+    },
+    FpArith {
+        op: FpArithOp,
+        precision: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rs2: FReg,
+        rm: Rounding,
+    },
+    FpSqrt {
+        precision: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rm: Rounding,
+    },
+    FpSign {
+        op: FpSignOp,
+        precision: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rs2: FReg,
+    },
+    /// `fmin` or `fmax`
+    FpMinMax {
+        max: bool,
+        precision: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rs2: FReg,
+    },
+    /// Conversion from the other precision to `to`
+    FpConvert {
+        to: Precision,
+        rd: FReg,
+        rs1: FReg,
+        rm: Rounding,
+    },
+    FpToInt {
+        precision: Precision,
+        int: IntFormat,
+        rd: Reg,
+        rs1: FReg,
+        rm: Rounding,
+    },
+    IntToFp {
+        precision: Precision,
+        int: IntFormat,
+        rd: FReg,
+        rs1: Reg,
+        rm: Rounding,
+    },
+    /// `fmv.x.w` or `fmv.x.d`
+    FpMoveToInt {
+        precision: Precision,
+        rd: Reg,
+        rs1: FReg,
+    },
+    /// `fmv.w.x` or `fmv.d.x`
+    FpMoveFromInt {
+        precision: Precision,
+        rd: FReg,
+        rs1: Reg,
+    },
+    FpCompare {
+        op: FpCompareOp,
+        precision: Precision,
+        rd: Reg,
+        rs1: FReg,
+        rs2: FReg,
+    },
+    FpClass {
+        precision: Precision,
+        rd: Reg,
+        rs1: FReg,
+    },
+    Sret,
+    Mret,
+    Wfi,
+    /// `sfence.vma`, `rs1` holds the address and `rs2` the ASID
+    SfenceVma {
+        rs1: Reg,
+        rs2: Reg,
+    },
+    /// `sinval.vma` (Svinval)
+    SinvalVma {
+        rs1: Reg,
+        rs2: Reg,
+    },
+    /// `sfence.w.inval` (Svinval)
+    SfenceWInval,
+    /// `sfence.inval.ir` (Svinval)
+    SfenceInvalIr,
+    /// `hfence.vvma` (H extension)
+    HfenceVvma {
+        rs1: Reg,
+        rs2: Reg,
+    },
+    /// `hfence.gvma` (H extension)
+    HfenceGvma {
+        rs1: Reg,
+        rs2: Reg,
+    },
+}
+
+/// Length in bytes of the instruction starting with the 16-bit `parcel`
+///
+/// Returns `None` for the encodings longer than 32 bits.
+#[inline]
+pub const fn instruction_len(parcel: u16) -> Option<usize> {
+    if parcel & 0b11 != 0b11 {
+        Some(2)
+    } else if parcel & 0b11100 != 0b11100 {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Decodes a 16-bit or 32-bit instruction
+///
+/// The length is given by the lowest bits: the upper 16 bits of `bits` are ignored for compressed
+/// instructions.
+#[inline]
+pub fn decode(bits: u32, xlen: Xlen) -> Option<Instruction> {
+    match instruction_len(bits as u16)? {
+        2 => decode_compressed(bits as u16, xlen),
+        _ => decode_full(bits, xlen),
+    }
+}
+
+/// Extracts `len` bits of `insn` starting at bit `lo`
+#[inline]
+const fn field(insn: u32, lo: u32, len: u32) -> u32 {
+    (insn >> lo) & ((1 << len) - 1)
+}
+
+/// Sign-extends the `len` lowest bits of `value`
+#[inline]
+const fn sext(value: u32, len: u32) -> i32 {
+    ((value << (32 - len)) as i32) >> (32 - len)
+}
+
+#[inline]
+fn rd(insn: u32) -> Reg {
+    Reg(field(insn, 7, 5) as u8)
+}
+
+#[inline]
+fn rs1(insn: u32) -> Reg {
+    Reg(field(insn, 15, 5) as u8)
+}
+
+#[inline]
+fn rs2(insn: u32) -> Reg {
+    Reg(field(insn, 20, 5) as u8)
+}
+
+#[inline]
+fn frd(insn: u32) -> FReg {
+    FReg(field(insn, 7, 5) as u8)
+}
+
+#[inline]
+fn frs1(insn: u32) -> FReg {
+    FReg(field(insn, 15, 5) as u8)
+}
+
+#[inline]
+fn frs2(insn: u32) -> FReg {
+    FReg(field(insn, 20, 5) as u8)
+}
+
+#[inline]
+fn frs3(insn: u32) -> FReg {
+    FReg(field(insn, 27, 5) as u8)
+}
+
+#[inline]
+fn imm_i(insn: u32) -> i32 {
+    (insn as i32) >> 20
+}
+
+#[inline]
+fn imm_s(insn: u32) -> i32 {
+    ((insn as i32) >> 25) << 5 | field(insn, 7, 5) as i32
+}
+
+#[inline]
+fn imm_b(insn: u32) -> i32 {
+    ((insn as i32) >> 31) << 12
+        | (field(insn, 7, 1) << 11 | field(insn, 25, 6) << 5 | field(insn, 8, 4) << 1) as i32
+}
+
+#[inline]
+fn imm_j(insn: u32) -> i32 {
+    ((insn as i32) >> 31) << 20
+        | (field(insn, 12, 8) << 12 | field(insn, 20, 1) << 11 | field(insn, 21, 10) << 1) as i32
+}
+
+#[inline]
+fn rounding(insn: u32) -> Option<Rounding> {
+    match field(insn, 12, 3) {
+        bits @ 0b000..=0b100 => Some(Rounding::Static(RoundingMode::from_bits(bits as usize))),
+        0b111 => Some(Rounding::Dynamic),
+        _ => None,
+    }
+}
+
+#[inline]
+fn fp_format(fmt: u32) -> Option<Precision> {
+    match fmt {
+        0b00 => Some(Precision::Single),
+        0b01 => Some(Precision::Double),
+        _ => None,
+    }
+}
+
+/// Decodes a 32-bit instruction
+pub fn decode_full(insn: u32, xlen: Xlen) -> Option<Instruction> {
+    let rv64 = xlen == Xlen::Rv64;
+    let funct3 = field(insn, 12, 3);
+    let funct7 = field(insn, 25, 7);
+    Some(match field(insn, 0, 7) {
+        0x37 => Instruction::Lui {
+            rd: rd(insn),
+            imm: (insn & 0xffff_f000) as i32,
+        },
+        0x17 => Instruction::Auipc {
+            rd: rd(insn),
+            imm: (insn & 0xffff_f000) as i32,
+        },
+        0x6f => Instruction::Jal {
+            rd: rd(insn),
+            offset: imm_j(insn),
+        },
+        0x67 if funct3 == 0 => Instruction::Jalr {
+            rd: rd(insn),
+            rs1: rs1(insn),
+            offset: imm_i(insn),
+        },
+        0x63 => Instruction::Branch {
+            cond: match funct3 {
+                0b000 => BranchCondition::Eq,
+                0b001 => BranchCondition::Ne,
+                0b100 => BranchCondition::Lt,
+                0b101 => BranchCondition::Ge,
+                0b110 => BranchCondition::Ltu,
+                0b111 => BranchCondition::Geu,
+                _ => return None,
+            },
+            rs1: rs1(insn),
+            rs2: rs2(insn),
+            offset: imm_b(insn),
+        },
+        0x03 => Instruction::Load {
+            width: match funct3 {
+                0b000 => LoadWidth::B,
+                0b001 => LoadWidth::H,
+                0b010 => LoadWidth::W,
+                0b011 if rv64 => LoadWidth::D,
+                0b100 => LoadWidth::Bu,
+                0b101 => LoadWidth::Hu,
+                0b110 if rv64 => LoadWidth::Wu,
+                _ => return None,
+            },
+            rd: rd(insn),
+            rs1: rs1(insn),
+            offset: imm_i(insn),
+        },
+        0x23 => Instruction::Store {
+            width: match funct3 {
+                0b000 => StoreWidth::B,
+                0b001 => StoreWidth::H,
+                0b010 => StoreWidth::W,
+                0b011 if rv64 => StoreWidth::D,
+                _ => return None,
+            },
+            rs1: rs1(insn),
+            rs2: rs2(insn),
+            offset: imm_s(insn),
+        },
+        0x13 => {
+            // The shift amount is 6 bits wide on RV64 and 5 bits wide on RV32, followed by a 6-bit
+            // or 7-bit function code (`0b010000` or `0b0100000` for `srai`)
+            let shamt_len = if rv64 { 6 } else { 5 };
+            let funct = field(insn, 20 + shamt_len, 12 - shamt_len);
+            let shamt = field(insn, 20, shamt_len) as i32;
+            let (op, imm) = match funct3 {
+                0b000 => (OpImm::Addi, imm_i(insn)),
+                0b010 => (OpImm::Slti, imm_i(insn)),
+                0b011 => (OpImm::Sltiu, imm_i(insn)),
+                0b100 => (OpImm::Xori, imm_i(insn)),
+                0b110 => (OpImm::Ori, imm_i(insn)),
+                0b111 => (OpImm::Andi, imm_i(insn)),
+                0b001 if funct == 0 => (OpImm::Slli, shamt),
+                0b101 if funct == 0 => (OpImm::Srli, shamt),
+                0b101 if funct == 0b0100000 >> (shamt_len - 5) => (OpImm::Srai, shamt),
+                _ => return None,
+            };
+            Instruction::OpImm {
+                op,
+                rd: rd(insn),
+                rs1: rs1(insn),
+                imm,
+            }
+        }
+        0x1b if rv64 => {
+            let shamt = field(insn, 20, 5) as i32;
+            let (op, imm) = match (funct3, funct7) {
+                (0b000, _) => (OpImm::Addiw, imm_i(insn)),
+                (0b001, 0b0000000) => (OpImm::Slliw, shamt),
+                (0b101, 0b0000000) => (OpImm::Srliw, shamt),
+                (0b101, 0b0100000) => (OpImm::Sraiw, shamt),
+                _ => return None,
+            };
+            Instruction::OpImm {
+                op,
+                rd: rd(insn),
+                rs1: rs1(insn),
+                imm,
+            }
+        }
+        0x33 => Instruction::Op {
+            op: match (funct7, funct3) {
+                (0b0000000, 0b000) => Op::Add,
+                (0b0100000, 0b000) => Op::Sub,
+                (0b0000000, 0b001) => Op::Sll,
+                (0b0000000, 0b010) => Op::Slt,
+                (0b0000000, 0b011) => Op::Sltu,
+                (0b0000000, 0b100) => Op::Xor,
+                (0b0000000, 0b101) => Op::Srl,
+                (0b0100000, 0b101) => Op::Sra,
+                (0b0000000, 0b110) => Op::Or,
+                (0b0000000, 0b111) => Op::And,
+                (0b0000001, 0b000) => Op::Mul,
+                (0b0000001, 0b001) => Op::Mulh,
+                (0b0000001, 0b010) => Op::Mulhsu,
+                (0b0000001, 0b011) => Op::Mulhu,
+                (0b0000001, 0b100) => Op::Div,
+                (0b0000001, 0b101) => Op::Divu,
+                (0b0000001, 0b110) => Op::Rem,
+                (0b0000001, 0b111) => Op::Remu,
+                _ => return None,
+            },
+            rd: rd(insn),
+            rs1: rs1(insn),
+            rs2: rs2(insn),
+        },
+        0x3b if rv64 => Instruction::Op {
+            op: match (funct7, funct3) {
+                (0b0000000, 0b000) => Op::Addw,
+                (0b0100000, 0b000) => Op::Subw,
+                (0b0000000, 0b001) => Op::Sllw,
+                (0b0000000, 0b101) => Op::Srlw,
+                (0b0100000, 0b101) => Op::Sraw,
+                (0b0000001, 0b000) => Op::Mulw,
+                (0b0000001, 0b100) => Op::Divw,
+                (0b0000001, 0b101) => Op::Divuw,
+                (0b0000001, 0b110) => Op::Remw,
+                (0b0000001, 0b111) => Op::Remuw,
+                _ => return None,
+            },
+            rd: rd(insn),
+            rs1: rs1(insn),
+            rs2: rs2(insn),
+        },
+        0x0f => match funct3 {
+            0b000 => Instruction::Fence {
+                fm: field(insn, 28, 4) as u8,
+                pred: FenceSet::from_bits(field(insn, 24, 4) as u8),
+                succ: FenceSet::from_bits(field(insn, 20, 4) as u8),
+            },
+            0b001 => Instruction::FenceI,
+            _ => return None,
+        },
+        0x73 => decode_system(insn)?,
+        0x2f => {
+            let width = match funct3 {
+                0b010 => AmoWidth::W,
+                0b011 if rv64 => AmoWidth::D,
+                _ => return None,
+            };
+            let (aq, rl) = (field(insn, 26, 1) != 0, field(insn, 25, 1) != 0);
+            let op = match field(insn, 27, 5) {
+                0b00010 if field(insn, 20, 5) == 0 => {
+                    return Some(Instruction::LoadReserved {
+                        width,
+                        rd: rd(insn),
+                        rs1: rs1(insn),
+                        aq,
+                        rl,
+                    })
+                }
+                0b00011 => {
+                    return Some(Instruction::StoreConditional {
+                        width,
+                        rd: rd(insn),
+                        rs1: rs1(insn),
+                        rs2: rs2(insn),
+                        aq,
+                        rl,
+                    })
+                }
+                0b00001 => AmoOp::Swap,
+                0b00000 => AmoOp::Add,
+                0b00100 => AmoOp::Xor,
+                0b01100 => AmoOp::And,
+                0b01000 => AmoOp::Or,
+                0b10000 => AmoOp::Min,
+                0b10100 => AmoOp::Max,
+                0b11000 => AmoOp::Minu,
+                0b11100 => AmoOp::Maxu,
+                _ => return None,
+            };
+            Instruction::Amo {
+                op,
+                width,
+                rd: rd(insn),
+                rs1: rs1(insn),
+                rs2: rs2(insn),
+                aq,
+                rl,
+            }
+        }
+        0x07 => Instruction::FpLoad {
+            precision: fp_format(funct3.checked_sub(0b010)?)?,
+            rd: frd(insn),
+            rs1: rs1(insn),
+            offset: imm_i(insn),
+        },
+        0x27 => Instruction::FpStore {
+            precision: fp_format(funct3.checked_sub(0b010)?)?,
+            rs1: rs1(insn),
+            rs2: frs2(insn),
+            offset: imm_s(insn),
+        },
+        opcode @ (0x43 | 0x47 | 0x4b | 0x4f) => Instruction::FpFma {
+            op: match opcode {
+                0x43 => FmaOp::Madd,
+                0x47 => FmaOp::Msub,
+                0x4b => FmaOp::Nmsub,
+                _ => FmaOp::Nmadd,
+            },
+            precision: fp_format(field(insn, 25, 2))?,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rs2: frs2(insn),
+            rs3: frs3(insn),
+            rm: rounding(insn)?,
+        },
+        0x53 => decode_op_fp(insn, rv64)?,
+        _ => return None,
+    })
+}
+
+/// Decodes the SYSTEM major opcode
+fn decode_system(insn: u32) -> Option<Instruction> {
+    let csr = field(insn, 20, 12) as u16;
+    let (op, src) = match field(insn, 12, 3) {
+        0b000 => {
+            return match insn {
+                0x0000_0073 => Some(Instruction::Ecall),
+                0x0010_0073 => Some(Instruction::Ebreak),
+                0x1020_0073 => Some(Instruction::Sret),
+                0x3020_0073 => Some(Instruction::Mret),
+                0x1050_0073 => Some(Instruction::Wfi),
+                0x1800_0073 => Some(Instruction::SfenceWInval),
+                0x1810_0073 => Some(Instruction::SfenceInvalIr),
+                _ if field(insn, 7, 5) != 0 => None,
+                _ => {
+                    let (rs1, rs2) = (rs1(insn), rs2(insn));
+                    match field(insn, 25, 7) {
+                        0b0001001 => Some(Instruction::SfenceVma { rs1, rs2 }),
+                        0b0001011 => Some(Instruction::SinvalVma { rs1, rs2 }),
+                        0b0010001 => Some(Instruction::HfenceVvma { rs1, rs2 }),
+                        0b0110001 => Some(Instruction::HfenceGvma { rs1, rs2 }),
+                        _ => None,
+                    }
+                }
+            };
+        }
+        0b001 => (CsrOp::ReadWrite, CsrSource::Reg(rs1(insn))),
+        0b010 => (CsrOp::ReadSet, CsrSource::Reg(rs1(insn))),
+        0b011 => (CsrOp::ReadClear, CsrSource::Reg(rs1(insn))),
+        0b101 => (CsrOp::ReadWrite, CsrSource::Imm(field(insn, 15, 5) as u8)),
+        0b110 => (CsrOp::ReadSet, CsrSource::Imm(field(insn, 15, 5) as u8)),
+        0b111 => (CsrOp::ReadClear, CsrSource::Imm(field(insn, 15, 5) as u8)),
+        _ => return None,
+    };
+    Some(Instruction::Csr {
+        op,
+        rd: rd(insn),
+        csr,
+        src,
+    })
+}
+
+/// Decodes the OP-FP major opcode
+fn decode_op_fp(insn: u32, rv64: bool) -> Option<Instruction> {
+    let precision = fp_format(field(insn, 25, 2))?;
+    let funct3 = field(insn, 12, 3);
+    let rs2_field = field(insn, 20, 5);
+    let int = |bits: u32| match bits {
+        0b00000 => Some(IntFormat::W),
+        0b00001 => Some(IntFormat::Wu),
+        0b00010 if rv64 => Some(IntFormat::L),
+        0b00011 if rv64 => Some(IntFormat::Lu),
+        _ => None,
+    };
+    let arith = |op| {
+        Some(Instruction::FpArith {
+            op,
+            precision,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rs2: frs2(insn),
+            rm: rounding(insn)?,
+        })
+    };
+    match field(insn, 27, 5) {
+        0b00000 => arith(FpArithOp::Add),
+        0b00001 => arith(FpArithOp::Sub),
+        0b00010 => arith(FpArithOp::Mul),
+        0b00011 => arith(FpArithOp::Div),
+        0b01011 if rs2_field == 0 => Some(Instruction::FpSqrt {
+            precision,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rm: rounding(insn)?,
+        }),
+        0b00100 => Some(Instruction::FpSign {
+            op: match funct3 {
+                0b000 => FpSignOp::SgnJ,
+                0b001 => FpSignOp::SgnJn,
+                0b010 => FpSignOp::SgnJx,
+                _ => return None,
+            },
+            precision,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rs2: frs2(insn),
+        }),
+        0b00101 if funct3 <= 0b001 => Some(Instruction::FpMinMax {
+            max: funct3 == 0b001,
+            precision,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rs2: frs2(insn),
+        }),
+        // fcvt.s.d and fcvt.d.s: `rs2` holds the source format, which must be the other one
+        0b01000 if fp_format(rs2_field)? != precision => Some(Instruction::FpConvert {
+            to: precision,
+            rd: frd(insn),
+            rs1: frs1(insn),
+            rm: rounding(insn)?,
+        }),
+        0b10100 => Some(Instruction::FpCompare {
+            op: match funct3 {
+                0b010 => FpCompareOp::Eq,
+                0b001 => FpCompareOp::Lt,
+                0b000 => FpCompareOp::Le,
+                _ => return None,
+            },
+            precision,
+            rd: rd(insn),
+            rs1: frs1(insn),
+            rs2: frs2(insn),
+        }),
+        0b11000 => Some(Instruction::FpToInt {
+            precision,
+            int: int(rs2_field)?,
+            rd: rd(insn),
+            rs1: frs1(insn),
+            rm: rounding(insn)?,
+        }),
+        0b11010 => Some(Instruction::IntToFp {
+            precision,
+            int: int(rs2_field)?,
+            rd: frd(insn),
+            rs1: rs1(insn),
+            rm: rounding(insn)?,
+        }),
+        0b11100 if rs2_field == 0 => match funct3 {
+            0b000 if precision == Precision::Single || rv64 => Some(Instruction::FpMoveToInt {
+                precision,
+                rd: rd(insn),
+                rs1: frs1(insn),
+            }),
+            0b001 => Some(Instruction::FpClass {
+                precision,
+                rd: rd(insn),
+                rs1: frs1(insn),
+            }),
+            _ => None,
+        },
+        0b11110 if rs2_field == 0 && funct3 == 0 && (precision == Precision::Single || rv64) => {
+            Some(Instruction::FpMoveFromInt {
+                precision,
+                rd: frd(insn),
+                rs1: rs1(insn),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Register `x8`-`x15` encoded in 3 bits of a compressed instruction
+#[inline]
+fn creg(insn: u32, lo: u32) -> Reg {
+    Reg(8 + field(insn, lo, 3) as u8)
+}
+
+/// Floating-point register `f8`-`f15` encoded in 3 bits of a compressed instruction
+#[inline]
+fn cfreg(insn: u32, lo: u32) -> FReg {
+    FReg(8 + field(insn, lo, 3) as u8)
+}
+
+/// Offset of `c.lw`, `c.sw`, `c.flw` and `c.fsw`
+#[inline]
+fn cw_offset(insn: u32) -> i32 {
+    (field(insn, 10, 3) << 3 | field(insn, 6, 1) << 2 | field(insn, 5, 1) << 6) as i32
+}
+
+/// Offset of `c.ld`, `c.sd`, `c.fld` and `c.fsd`
+#[inline]
+fn cd_offset(insn: u32) -> i32 {
+    (field(insn, 10, 3) << 3 | field(insn, 5, 2) << 6) as i32
+}
+
+/// Offset of `c.lwsp` and `c.flwsp`
+#[inline]
+fn clwsp_offset(insn: u32) -> i32 {
+    (field(insn, 12, 1) << 5 | field(insn, 4, 3) << 2 | field(insn, 2, 2) << 6) as i32
+}
+
+/// Offset of `c.ldsp` and `c.fldsp`
+#[inline]
+fn cldsp_offset(insn: u32) -> i32 {
+    (field(insn, 12, 1) << 5 | field(insn, 5, 2) << 3 | field(insn, 2, 3) << 6) as i32
+}
+
+/// Offset of `c.swsp` and `c.fswsp`
+#[inline]
+fn cswsp_offset(insn: u32) -> i32 {
+    (field(insn, 9, 4) << 2 | field(insn, 7, 2) << 6) as i32
+}
+
+/// Offset of `c.sdsp` and `c.fsdsp`
+#[inline]
+fn csdsp_offset(insn: u32) -> i32 {
+    (field(insn, 10, 3) << 3 | field(insn, 7, 3) << 6) as i32
+}
+
+/// 6-bit signed immediate of `c.addi`, `c.li`, `c.andi` and `c.addiw`
+#[inline]
+fn ci_imm(insn: u32) -> i32 {
+    sext(field(insn, 12, 1) << 5 | field(insn, 2, 5), 6)
+}
+
+/// Offset of `c.j` and `c.jal`
+#[inline]
+fn cj_offset(insn: u32) -> i32 {
+    sext(
+        field(insn, 12, 1) << 11
+            | field(insn, 11, 1) << 4
+            | field(insn, 9, 2) << 8
+            | field(insn, 8, 1) << 10
+            | field(insn, 7, 1) << 6
+            | field(insn, 6, 1) << 7
+            | field(insn, 3, 3) << 1
+            | field(insn, 2, 1) << 5,
+        12,
+    )
+}
+
+/// Offset of `c.beqz` and `c.bnez`
+#[inline]
+fn cb_offset(insn: u32) -> i32 {
+    sext(
+        field(insn, 12, 1) << 8
+            | field(insn, 10, 2) << 3
+            | field(insn, 5, 2) << 6
+            | field(insn, 3, 2) << 1
+            | field(insn, 2, 1) << 5,
+        9,
+    )
+}
+
+/// Decodes a 16-bit compressed instruction into the equivalent 32-bit instruction
+pub fn decode_compressed(insn: u16, xlen: Xlen) -> Option<Instruction> {
+    let rv64 = xlen == Xlen::Rv64;
+    let insn = insn as u32;
+    let funct3 = field(insn, 13, 3);
+    let rd_full = Reg(field(insn, 7, 5) as u8);
+    let rs2_full = Reg(field(insn, 2, 5) as u8);
+    let shamt = field(insn, 12, 1) << 5 | field(insn, 2, 5);
+    Some(match (field(insn, 0, 2), funct3) {
+        // Quadrant 0
+        (0b00, 0b000) => {
+            let imm = field(insn, 11, 2) << 4
+                | field(insn, 7, 4) << 6
+                | field(insn, 6, 1) << 2
+                | field(insn, 5, 1) << 3;
+            if imm == 0 {
+                return None;
+            }
+            Instruction::OpImm {
+                op: OpImm::Addi,
+                rd: creg(insn, 2),
+                rs1: Reg::SP,
+                imm: imm as i32,
+            }
+        }
+        (0b00, 0b001) => Instruction::FpLoad {
+            precision: Precision::Double,
+            rd: cfreg(insn, 2),
+            rs1: creg(insn, 7),
+            offset: cd_offset(insn),
+        },
+        (0b00, 0b010) => Instruction::Load {
+            width: LoadWidth::W,
+            rd: creg(insn, 2),
+            rs1: creg(insn, 7),
+            offset: cw_offset(insn),
+        },
+        (0b00, 0b011) if rv64 => Instruction::Load {
+            width: LoadWidth::D,
+            rd: creg(insn, 2),
+            rs1: creg(insn, 7),
+            offset: cd_offset(insn),
+        },
+        (0b00, 0b011) => Instruction::FpLoad {
+            precision: Precision::Single,
+            rd: cfreg(insn, 2),
+            rs1: creg(insn, 7),
+            offset: cw_offset(insn),
+        },
+        (0b00, 0b101) => Instruction::FpStore {
+            precision: Precision::Double,
+            rs1: creg(insn, 7),
+            rs2: cfreg(insn, 2),
+            offset: cd_offset(insn),
+        },
+        (0b00, 0b110) => Instruction::Store {
+            width: StoreWidth::W,
+            rs1: creg(insn, 7),
+            rs2: creg(insn, 2),
+            offset: cw_offset(insn),
+        },
+        (0b00, 0b111) if rv64 => Instruction::Store {
+            width: StoreWidth::D,
+            rs1: creg(insn, 7),
+            rs2: creg(insn, 2),
+            offset: cd_offset(insn),
+        },
+        (0b00, 0b111) => Instruction::FpStore {
+            precision: Precision::Single,
+            rs1: creg(insn, 7),
+            rs2: cfreg(insn, 2),
+            offset: cw_offset(insn),
+        },
+
+        // Quadrant 1
+        (0b01, 0b000) => Instruction::OpImm {
+            op: OpImm::Addi,
+            rd: rd_full,
+            rs1: rd_full,
+            imm: ci_imm(insn),
+        },
+        (0b01, 0b001) if rv64 => {
+            if rd_full == Reg::ZERO {
+                return None;
+            }
+            Instruction::OpImm {
+                op: OpImm::Addiw,
+                rd: rd_full,
+                rs1: rd_full,
+                imm: ci_imm(insn),
+            }
+        }
+        (0b01, 0b001) => Instruction::Jal {
+            rd: Reg::RA,
+            offset: cj_offset(insn),
+        },
+        (0b01, 0b010) => Instruction::OpImm {
+            op: OpImm::Addi,
+            rd: rd_full,
+            rs1: Reg::ZERO,
+            imm: ci_imm(insn),
+        },
+        (0b01, 0b011) if rd_full == Reg::SP => {
+            let imm = sext(
+                field(insn, 12, 1) << 9
+                    | field(insn, 6, 1) << 4
+                    | field(insn, 5, 1) << 6
+                    | field(insn, 3, 2) << 7
+                    | field(insn, 2, 1) << 5,
+                10,
+            );
+            if imm == 0 {
+                return None;
+            }
+            Instruction::OpImm {
+                op: OpImm::Addi,
+                rd: Reg::SP,
+                rs1: Reg::SP,
+                imm,
+            }
+        }
+        (0b01, 0b011) => {
+            let imm = ci_imm(insn) << 12;
+            if imm == 0 {
+                return None;
+            }
+            Instruction::Lui { rd: rd_full, imm }
+        }
+        (0b01, 0b100) => {
+            let rd = creg(insn, 7);
+            let op_imm = |op, imm| Instruction::OpImm {
+                op,
+                rd,
+                rs1: rd,
+                imm,
+            };
+            let op = |op| Instruction::Op {
+                op,
+                rd,
+                rs1: rd,
+                rs2: creg(insn, 2),
+            };
+            match (field(insn, 10, 2), field(insn, 12, 1), field(insn, 5, 2)) {
+                (0b00, _, _) if rv64 || shamt < 32 => op_imm(OpImm::Srli, shamt as i32),
+                (0b01, _, _) if rv64 || shamt < 32 => op_imm(OpImm::Srai, shamt as i32),
+                (0b10, _, _) => op_imm(OpImm::Andi, ci_imm(insn)),
+                (0b11, 0, 0b00) => op(Op::Sub),
+                (0b11, 0, 0b01) => op(Op::Xor),
+                (0b11, 0, 0b10) => op(Op::Or),
+                (0b11, 0, 0b11) => op(Op::And),
+                (0b11, 1, 0b00) if rv64 => op(Op::Subw),
+                (0b11, 1, 0b01) if rv64 => op(Op::Addw),
+                _ => return None,
+            }
+        }
+        (0b01, 0b101) => Instruction::Jal {
+            rd: Reg::ZERO,
+            offset: cj_offset(insn),
+        },
+        (0b01, 0b110) | (0b01, 0b111) => Instruction::Branch {
+            cond: if funct3 == 0b110 {
+                BranchCondition::Eq
+            } else {
+                BranchCondition::Ne
+            },
+            rs1: creg(insn, 7),
+            rs2: Reg::ZERO,
+            offset: cb_offset(insn),
+        },
+
+        // Quadrant 2
+        (0b10, 0b000) if rv64 || shamt < 32 => Instruction::OpImm {
+            op: OpImm::Slli,
+            rd: rd_full,
+            rs1: rd_full,
+            imm: shamt as i32,
+        },
+        (0b10, 0b001) => Instruction::FpLoad {
+            precision: Precision::Double,
+            rd: FReg(rd_full.0),
+            rs1: Reg::SP,
+            offset: cldsp_offset(insn),
+        },
+        (0b10, 0b010) if rd_full != Reg::ZERO => Instruction::Load {
+            width: LoadWidth::W,
+            rd: rd_full,
+            rs1: Reg::SP,
+            offset: clwsp_offset(insn),
+        },
+        (0b10, 0b011) if rv64 && rd_full != Reg::ZERO => Instruction::Load {
+            width: LoadWidth::D,
+            rd: rd_full,
+            rs1: Reg::SP,
+            offset: cldsp_offset(insn),
+        },
+        (0b10, 0b011) if !rv64 => Instruction::FpLoad {
+            precision: Precision::Single,
+            rd: FReg(rd_full.0),
+            rs1: Reg::SP,
+            offset: clwsp_offset(insn),
+        },
+        (0b10, 0b100) => match (field(insn, 12, 1), rd_full, rs2_full) {
+            (0, Reg::ZERO, Reg::ZERO) => return None,
+            (0, rs1, Reg::ZERO) => Instruction::Jalr {
+                rd: Reg::ZERO,
+                rs1,
+                offset: 0,
+            },
+            (0, rd, rs2) => Instruction::Op {
+                op: Op::Add,
+                rd,
+                rs1: Reg::ZERO,
+                rs2,
+            },
+            (_, Reg::ZERO, Reg::ZERO) => Instruction::Ebreak,
+            (_, rs1, Reg::ZERO) => Instruction::Jalr {
+                rd: Reg::RA,
+                rs1,
+                offset: 0,
+            },
+            (_, rd, rs2) => Instruction::Op {
+                op: Op::Add,
+                rd,
+                rs1: rd,
+                rs2,
+            },
+        },
+        (0b10, 0b101) => Instruction::FpStore {
+            precision: Precision::Double,
+            rs1: Reg::SP,
+            rs2: FReg(rs2_full.0),
+            offset: csdsp_offset(insn),
+        },
+        (0b10, 0b110) => Instruction::Store {
+            width: StoreWidth::W,
+            rs1: Reg::SP,
+            rs2: rs2_full,
+            offset: cswsp_offset(insn),
+        },
+        (0b10, 0b111) if rv64 => Instruction::Store {
+            width: StoreWidth::D,
+            rs1: Reg::SP,
+            rs2: rs2_full,
+            offset: csdsp_offset(insn),
+        },
+        (0b10, 0b111) => Instruction::FpStore {
+            precision: Precision::Single,
+            rs1: Reg::SP,
+            rs2: FReg(rs2_full.0),
+            offset: cswsp_offset(insn),
+        },
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instruction::{
+        Amo, Auipc, Branch, Csr, Ebreak, Fence, FenceI, FpArith, FpClass, FpCompare, FpConvert,
+        FpFma, FpLoad, FpMinMax, FpMoveFromInt, FpMoveToInt, FpSign, FpSqrt, FpStore, FpToInt,
+        IntToFp, Jal, Jalr, Load, LoadReserved, Lui, Mret, SfenceVma, Store, StoreConditional, Wfi,
+    };
+
+    fn x(index: u8) -> Reg {
+        Reg::new(index)
+    }
+
+    fn f(index: u8) -> FReg {
+        FReg::new(index)
+    }
+
+    const RTZ: Rounding = Rounding::Static(RoundingMode::RoundTowardsZero);
+    const RUP: Rounding = Rounding::Static(RoundingMode::RoundUp);
+    const RNE: Rounding = Rounding::Static(RoundingMode::RoundToNearestEven);
+
+    fn check(xlen: Xlen, cases: &[([u8; 4], Instruction)]) {
+        for &(bytes, expected) in cases {
+            let bits = u32::from_le_bytes(bytes);
+            assert_eq!(decode(bits, xlen), Some(expected), "{:#010x}", bits);
+        }
+    }
+
+    fn check_compressed(xlen: Xlen, cases: &[([u8; 2], Instruction)]) {
+        for &(bytes, expected) in cases {
+            let bits = u16::from_le_bytes(bytes);
+            assert_eq!(instruction_len(bits), Some(2));
+            assert_eq!(decode(bits as u32, xlen), Some(expected), "{:#06x}", bits);
+        }
+    }
+
+    #[test]
+    fn base() {
+        #[rustfmt::skip]
+        check(Xlen::Rv64, &[
+            ([0x37, 0xf5, 0xff, 0xff], Lui { rd: x(10), imm: -4096 }),
+            ([0x97, 0x12, 0x00, 0x00], Auipc { rd: x(5), imm: 4096 }),
+            ([0xef, 0xf0, 0x1f, 0x80], Jal { rd: x(1), offset: -2048 }),
+            ([0x67, 0x80, 0xc5, 0x00], Jalr { rd: x(0), rs1: x(11), offset: 12 }),
+            ([0xe3, 0x68, 0xb5, 0xfe], Branch { cond: BranchCondition::Ltu, rs1: x(10), rs2: x(11), offset: -16 }),
+            ([0x83, 0x54, 0xc4, 0xff], Load { width: LoadWidth::Hu, rd: x(9), rs1: x(8), offset: -4 }),
+            ([0x23, 0x3c, 0xc1, 0x7e], Store { width: StoreWidth::D, rs1: x(2), rs2: x(12), offset: 2040 }),
+            ([0x13, 0xd5, 0x85, 0x42], Instruction::OpImm { op: OpImm::Srai, rd: x(10), rs1: x(11), imm: 40 }),
+            ([0x1b, 0xd5, 0x35, 0x40], Instruction::OpImm { op: OpImm::Sraiw, rd: x(10), rs1: x(11), imm: 3 }),
+            ([0x33, 0xa3, 0x23, 0x03], Instruction::Op { op: Op::Mulhsu, rd: x(6), rs1: x(7), rs2: x(18) }),
+            ([0x3b, 0xf5, 0xc5, 0x02], Instruction::Op { op: Op::Remuw, rd: x(10), rs1: x(11), rs2: x(12) }),
+            ([0x0f, 0x00, 0x30, 0x83], Fence { fm: 0b1000, pred: FenceSet::RW, succ: FenceSet::RW }),
+            ([0x0f, 0x00, 0x10, 0x02], Fence { fm: 0, pred: FenceSet::R, succ: FenceSet::W }),
+            ([0x0f, 0x10, 0x00, 0x00], FenceI),
+            ([0x73, 0x25, 0x00, 0x30], Csr { op: CsrOp::ReadSet, rd: x(10), csr: 0x300, src: CsrSource::Reg(x(0)) }),
+            ([0x73, 0x70, 0x44, 0x30], Csr { op: CsrOp::ReadClear, rd: x(0), csr: 0x304, src: CsrSource::Imm(8) }),
+            ([0x73, 0x00, 0x20, 0x30], Mret),
+            ([0x73, 0x00, 0x50, 0x10], Wfi),
+            ([0x73, 0x00, 0xb5, 0x12], SfenceVma { rs1: x(10), rs2: x(11) }),
+        ]);
+    }
+
+    #[test]
+    fn atomic() {
+        #[rustfmt::skip]
+        check(Xlen::Rv64, &[
+            ([0x2f, 0xb5, 0x05, 0x14], LoadReserved { width: AmoWidth::D, rd: x(10), rs1: x(11), aq: true, rl: false }),
+            ([0x2f, 0x26, 0xd7, 0x1a], StoreConditional { width: AmoWidth::W, rd: x(12), rs1: x(14), rs2: x(13), aq: false, rl: true }),
+            ([0x2f, 0x35, 0xb6, 0xe6], Amo { op: AmoOp::Maxu, width: AmoWidth::D, rd: x(10), rs1: x(12), rs2: x(11), aq: true, rl: true }),
+        ]);
+    }
+
+    #[test]
+    fn floating_point() {
+        let (s, d) = (Precision::Single, Precision::Double);
+        #[rustfmt::skip]
+        check(Xlen::Rv64, &[
+            ([0x07, 0x35, 0x05, 0x01], FpLoad { precision: d, rd: f(10), rs1: x(10), offset: 16 }),
+            ([0x27, 0x2c, 0x11, 0xfe], FpStore { precision: s, rs1: x(2), rs2: f(1), offset: -8 }),
+            ([0x4f, 0x95, 0xc5, 0x6a], FpFma { op: FmaOp::Nmadd, precision: d, rd: f(10), rs1: f(11), rs2: f(12), rs3: f(13), rm: RTZ }),
+            ([0x53, 0xf0, 0x20, 0x18], FpArith { op: FpArithOp::Div, precision: s, rd: f(0), rs1: f(1), rs2: f(2), rm: Rounding::Dynamic }),
+            ([0x53, 0xf5, 0x05, 0x5a], FpSqrt { precision: d, rd: f(10), rs1: f(11), rm: Rounding::Dynamic }),
+            ([0x53, 0xa5, 0xc5, 0x20], FpSign { op: FpSignOp::SgnJx, precision: s, rd: f(10), rs1: f(11), rs2: f(12) }),
+            ([0x53, 0x95, 0xc5, 0x2a], FpMinMax { max: true, precision: d, rd: f(10), rs1: f(11), rs2: f(12) }),
+            ([0x53, 0xf5, 0x15, 0x40], FpConvert { to: s, rd: f(10), rs1: f(11), rm: Rounding::Dynamic }),
+            ([0x53, 0xb5, 0x35, 0xc2], FpToInt { precision: d, int: IntFormat::Lu, rd: x(10), rs1: f(11), rm: RUP }),
+            ([0x53, 0x85, 0x05, 0xd2], IntToFp { precision: d, int: IntFormat::W, rd: f(10), rs1: x(11), rm: RNE }),
+            ([0x53, 0x05, 0x05, 0xe2], FpMoveToInt { precision: d, rd: x(10), rs1: f(10) }),
+            ([0x53, 0x05, 0x05, 0xf0], FpMoveFromInt { precision: s, rd: f(10), rs1: x(10) }),
+            ([0x53, 0x15, 0xb5, 0xa2], FpCompare { op: FpCompareOp::Lt, precision: d, rd: x(10), rs1: f(10), rs2: f(11) }),
+            ([0x53, 0x15, 0x05, 0xe0], FpClass { precision: s, rd: x(10), rs1: f(10) }),
+        ]);
+    }
+
+    #[test]
+    fn compressed_rv64() {
+        let sp = Reg::SP;
+        #[rustfmt::skip]
+        check_compressed(Xlen::Rv64, &[
+            ([0xe8, 0x1f], Instruction::OpImm { op: OpImm::Addi, rd: x(10), rs1: sp, imm: 1020 }),
+            ([0xe8, 0x3f], FpLoad { precision: Precision::Double, rd: f(10), rs1: x(15), offset: 248 }),
+            ([0x6c, 0x5e], Load { width: LoadWidth::W, rd: x(11), rs1: x(12), offset: 124 }),
+            ([0x80, 0x64], Load { width: LoadWidth::D, rd: x(8), rs1: x(9), offset: 8 }),
+            ([0x00, 0xa1], FpStore { precision: Precision::Double, rs1: x(10), rs2: f(8), offset: 0 }),
+            ([0x3c, 0xc3], Store { width: StoreWidth::W, rs1: x(14), rs2: x(15), offset: 64 }),
+            ([0xe8, 0xfd], Store { width: StoreWidth::D, rs1: x(11), rs2: x(10), offset: 248 }),
+            ([0x01, 0x00], Instruction::OpImm { op: OpImm::Addi, rd: x(0), rs1: x(0), imm: 0 }),
+            ([0x01, 0x15], Instruction::OpImm { op: OpImm::Addi, rd: x(10), rs1: x(10), imm: -32 }),
+            ([0xfd, 0x25], Instruction::OpImm { op: OpImm::Addiw, rd: x(11), rs1: x(11), imm: 31 }),
+            ([0xfd, 0x52], Instruction::OpImm { op: OpImm::Addi, rd: x(5), rs1: x(0), imm: -1 }),
+            ([0x01, 0x71], Instruction::OpImm { op: OpImm::Addi, rd: sp, rs1: sp, imm: -512 }),
+            ([0x85, 0x76], Lui { rd: x(13), imm: -0x1f000 }),
+            ([0x7d, 0x91], Instruction::OpImm { op: OpImm::Srli, rd: x(10), rs1: x(10), imm: 63 }),
+            ([0x85, 0x84], Instruction::OpImm { op: OpImm::Srai, rd: x(9), rs1: x(9), imm: 1 }),
+            ([0x61, 0x9a], Instruction::OpImm { op: OpImm::Andi, rd: x(12), rs1: x(12), imm: -8 }),
+            ([0x0d, 0x8d], Instruction::Op { op: Op::Sub, rd: x(10), rs1: x(10), rs2: x(11) }),
+            ([0x25, 0x8c], Instruction::Op { op: Op::Xor, rd: x(8), rs1: x(8), rs2: x(9) }),
+            ([0x55, 0x8e], Instruction::Op { op: Op::Or, rd: x(12), rs1: x(12), rs2: x(13) }),
+            ([0x7d, 0x8f], Instruction::Op { op: Op::And, rd: x(14), rs1: x(14), rs2: x(15) }),
+            ([0x0d, 0x9d], Instruction::Op { op: Op::Subw, rd: x(10), rs1: x(10), rs2: x(11) }),
+            ([0x35, 0x9e], Instruction::Op { op: Op::Addw, rd: x(12), rs1: x(12), rs2: x(13) }),
+            ([0x01, 0xb0], Jal { rd: x(0), offset: -2048 }),
+            ([0x01, 0xd1], Branch { cond: BranchCondition::Eq, rs1: x(10), rs2: x(0), offset: -256 }),
+            ([0xfd, 0xec], Branch { cond: BranchCondition::Ne, rs1: x(9), rs2: x(0), offset: 254 }),
+            ([0x7e, 0x15], Instruction::OpImm { op: OpImm::Slli, rd: x(10), rs1: x(10), imm: 63 }),
+            ([0x7e, 0x35], FpLoad { precision: Precision::Double, rd: f(10), rs1: sp, offset: 504 }),
+            ([0xfe, 0x50], Load { width: LoadWidth::W, rd: x(1), rs1: sp, offset: 252 }),
+            ([0x7e, 0x74], Load { width: LoadWidth::D, rd: x(8), rs1: sp, offset: 504 }),
+            ([0x82, 0x80], Jalr { rd: x(0), rs1: x(1), offset: 0 }),
+            ([0x2e, 0x85], Instruction::Op { op: Op::Add, rd: x(10), rs1: x(0), rs2: x(11) }),
+            ([0x02, 0x90], Ebreak),
+            ([0x82, 0x92], Jalr { rd: x(1), rs1: x(5), offset: 0 }),
+            ([0x2e, 0x95], Instruction::Op { op: Op::Add, rd: x(10), rs1: x(10), rs2: x(11) }),
+            ([0xae, 0xbf], FpStore { precision: Precision::Double, rs1: sp, rs2: f(11), offset: 504 }),
+            ([0x86, 0xdf], Store { width: StoreWidth::W, rs1: sp, rs2: x(1), offset: 252 }),
+            ([0xa2, 0xff], Store { width: StoreWidth::D, rs1: sp, rs2: x(8), offset: 504 }),
+        ]);
+    }
+
+    #[test]
+    fn compressed_rv32() {
+        let sp = Reg::SP;
+        #[rustfmt::skip]
+        check_compressed(Xlen::Rv32, &[
+            ([0xe8, 0x7f], FpLoad { precision: Precision::Single, rd: f(10), rs1: x(15), offset: 124 }),
+            ([0x4c, 0xe1], FpStore { precision: Precision::Single, rs1: x(10), rs2: f(11), offset: 4 }),
+            ([0xfd, 0x2f], Jal { rd: x(1), offset: 2046 }),
+            ([0x7e, 0x76], FpLoad { precision: Precision::Single, rd: f(12), rs1: sp, offset: 252 }),
+            ([0xb6, 0xff], FpStore { precision: Precision::Single, rs1: sp, rs2: f(13), offset: 252 }),
+        ]);
+    }
+
+    #[test]
+    fn illegal() {
+        // All zeros, all ones and reserved compressed encodings
+        assert_eq!(decode(0x0000, Xlen::Rv64), None);
+        assert_eq!(decode(0xffff_ffff, Xlen::Rv64), None);
+        assert_eq!(instruction_len(0xffff), None);
+        assert_eq!(decode(0x8082 & !0x0080, Xlen::Rv64), None); // c.jr x0
+
+        // RV64-only instructions on RV32
+        assert_eq!(decode(0x7ec13c23, Xlen::Rv32), None); // sd
+        assert_eq!(decode(0x4035d51b, Xlen::Rv32), None); // sraiw
+        assert_eq!(decode(0x4285d513, Xlen::Rv32), None); // srai with a shift amount of 40
+        assert_eq!(decode(0x917d, Xlen::Rv32), None); // c.srli with a shift amount of 63
+        assert_eq!(decode(0xe2050553, Xlen::Rv32), None); // fmv.x.d
+
+        // Reserved rounding modes
+        assert_eq!(decode(0x1820_d053, Xlen::Rv64), None);
+    }
+}
@@ -1,87 +1,181 @@
 //! Delay devices and providers
-use crate::register::mcycle;
-use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+//!
+//! The providers implement the `DelayNs` trait of embedded-hal 1.0. The `DelayUs` and `DelayMs`
+//! traits of embedded-hal 0.2 are also implemented when the `embedded-hal-02` feature is enabled.
+use crate::register::{mcycle, time};
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "embedded-hal-02")]
+use embedded_hal_02::blocking::delay::{DelayMs, DelayUs};
+
+/// Converts `count` units of `1 / units_second` seconds into ticks of a `ticks_second` Hz counter
+#[inline]
+pub(crate) fn ticks(count: u64, units_second: u64, ticks_second: u64) -> u64 {
+    ((count as u128 * ticks_second as u128) / units_second as u128) as u64
+}
+
+/// Spins until `counter` has advanced by more than `ticks`
+#[inline]
+fn busy_wait(counter: fn() -> u64, ticks: u64) {
+    let t0 = counter();
+    while counter().wrapping_sub(t0) <= ticks {}
+}
 
 /// Machine mode cycle counter (`mcycle`) as a delay provider
+///
+/// Only usable in M-mode. The delays are only accurate if the core frequency is constant.
 #[derive(Copy, Clone)]
 pub struct McycleDelay {
-    ticks_second: u32,
+    ticks_second: u64,
 }
 
 impl McycleDelay {
     /// Constructs the delay provider.
     /// `ticks_second` should be the clock speed of the core, in Hertz
     #[inline(always)]
-    pub fn new(ticks_second: u32) -> Self {
+    pub fn new(ticks_second: u64) -> Self {
         Self { ticks_second }
     }
 }
 
+impl DelayNs for McycleDelay {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        busy_wait(
+            mcycle::read64,
+            ticks(ns as u64, 1_000_000_000, self.ticks_second),
+        );
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        busy_wait(
+            mcycle::read64,
+            ticks(us as u64, 1_000_000, self.ticks_second),
+        );
+    }
+
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        busy_wait(mcycle::read64, ticks(ms as u64, 1_000, self.ticks_second));
+    }
+}
+
+/// Real-time counter (`time`) as a delay provider
+///
+/// Usable from any privilege mode that has access to the `time` CSR (see `mcounteren::set_tm` and
+/// `scounteren::set_tm`), and independent of the core frequency.
+#[derive(Copy, Clone)]
+pub struct TimeDelay {
+    timebase_frequency: u64,
+}
+
+impl TimeDelay {
+    /// Constructs the delay provider.
+    /// `timebase_frequency` should be the frequency of the `time` counter, in Hertz
+    #[inline(always)]
+    pub fn new(timebase_frequency: u64) -> Self {
+        Self { timebase_frequency }
+    }
+}
+
+impl DelayNs for TimeDelay {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        busy_wait(
+            time::read64,
+            ticks(ns as u64, 1_000_000_000, self.timebase_frequency),
+        );
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        busy_wait(
+            time::read64,
+            ticks(us as u64, 1_000_000, self.timebase_frequency),
+        );
+    }
+
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        busy_wait(
+            time::read64,
+            ticks(ms as u64, 1_000, self.timebase_frequency),
+        );
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
 impl DelayUs<u64> for McycleDelay {
     #[inline]
     fn delay_us(&mut self, us: u64) {
-        let t0 = mcycle::read64();
-        let clock = (us * (self.ticks_second as u64)) / 1_000_000;
-        while mcycle::read64().wrapping_sub(t0) <= clock {}
+        busy_wait(mcycle::read64, ticks(us, 1_000_000, self.ticks_second));
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayUs<u32> for McycleDelay {
     #[inline(always)]
     fn delay_us(&mut self, us: u32) {
-        self.delay_us(us as u64)
+        DelayUs::<u64>::delay_us(self, us as u64)
     }
 }
 
 // Implemented for constructions like `delay.delay_us(50_000);`
+#[cfg(feature = "embedded-hal-02")]
 impl DelayUs<i32> for McycleDelay {
     #[inline(always)]
     fn delay_us(&mut self, us: i32) {
         assert!(us >= 0);
-        self.delay_us(us as u32);
+        DelayUs::<u32>::delay_us(self, us as u32);
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayUs<u16> for McycleDelay {
     #[inline(always)]
     fn delay_us(&mut self, us: u16) {
-        self.delay_us(us as u32)
+        DelayUs::<u32>::delay_us(self, us as u32)
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayUs<u8> for McycleDelay {
     #[inline(always)]
     fn delay_us(&mut self, us: u8) {
-        self.delay_us(us as u32)
+        DelayUs::<u32>::delay_us(self, us as u32)
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayMs<u32> for McycleDelay {
     #[inline]
     fn delay_ms(&mut self, ms: u32) {
-        self.delay_us((ms as u64) * 1000)
+        DelayUs::<u64>::delay_us(self, (ms as u64) * 1000)
     }
 }
 
 // Implemented for constructions like `delay.delay_ms(50_000);`
+#[cfg(feature = "embedded-hal-02")]
 impl DelayMs<i32> for McycleDelay {
     #[inline(always)]
     fn delay_ms(&mut self, ms: i32) {
         assert!(ms >= 0);
-        self.delay_ms(ms as u32);
+        DelayMs::<u32>::delay_ms(self, ms as u32);
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayMs<u16> for McycleDelay {
     #[inline(always)]
     fn delay_ms(&mut self, ms: u16) {
-        self.delay_ms(ms as u32)
+        DelayMs::<u32>::delay_ms(self, ms as u32)
     }
 }
 
+#[cfg(feature = "embedded-hal-02")]
 impl DelayMs<u8> for McycleDelay {
     #[inline(always)]
     fn delay_ms(&mut self, ms: u8) {
-        self.delay_ms(ms as u32)
+        DelayMs::<u32>::delay_ms(self, ms as u32)
     }
 }
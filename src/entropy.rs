@@ -0,0 +1,218 @@
+//! Random numbers from the Zkr entropy source
+//!
+//! The samples of the `seed` CSR are raw entropy: `SeedRng` conditions them with SHA-256,
+//! compressing 32 samples (512 bits) into each 256-bit output block, and implements the
+//! `rand_core` `RngCore` and `CryptoRng` traits. It is meant to seed other generators or provide
+//! the few random values needed at boot (stack canaries, address space layout randomization), as
+//! each output block takes 32 reads of `seed`.
+//!
+//! The entropy source reports `BIST` and `WAIT` while no sample is ready: `SeedRng` keeps polling
+//! it, up to a configurable number of times, and reports a `DEAD` source as an error.
+
+use crate::register::seed::{self, Opst, Seed};
+use core::num::NonZeroU32;
+use sha2::{Digest, Sha256};
+
+/// Default number of consecutive `BIST` or `WAIT` polls after which `SeedRng` gives up
+pub const DEFAULT_RETRIES: u32 = 100_000;
+
+/// Number of samples conditioned into each output block
+const SAMPLES_PER_BLOCK: usize = 32;
+
+/// Entropy source error
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The entropy source reported an unrecoverable self-test failure (`DEAD`)
+    Dead,
+    /// The entropy source did not produce a sample within the retry limit
+    Timeout,
+}
+
+impl Error {
+    /// `rand_core` error code of `Error::Dead`
+    pub const DEAD_CODE: u32 = rand_core::Error::CUSTOM_START;
+    /// `rand_core` error code of `Error::Timeout`
+    pub const TIMEOUT_CODE: u32 = rand_core::Error::CUSTOM_START + 1;
+
+    /// Converts a `rand_core` error code back to an `Error`
+    #[inline]
+    pub fn from_code(code: NonZeroU32) -> Option<Self> {
+        match code.get() {
+            Self::DEAD_CODE => Some(Self::Dead),
+            Self::TIMEOUT_CODE => Some(Self::Timeout),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for rand_core::Error {
+    #[inline]
+    fn from(error: Error) -> Self {
+        let code = match error {
+            Error::Dead => Error::DEAD_CODE,
+            Error::Timeout => Error::TIMEOUT_CODE,
+        };
+        // Safety: both codes are above `CUSTOM_START`
+        unsafe { NonZeroU32::new_unchecked(code) }.into()
+    }
+}
+
+/// Cryptographically secure random number generator reading the `seed` CSR
+#[derive(Clone, Debug)]
+pub struct SeedRng {
+    retries: u32,
+}
+
+impl SeedRng {
+    /// Constructs a generator polling the entropy source up to `DEFAULT_RETRIES` times per sample
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_retries(DEFAULT_RETRIES)
+    }
+
+    /// Constructs a generator polling the entropy source up to `retries` times per sample
+    #[inline]
+    pub const fn with_retries(retries: u32) -> Self {
+        Self { retries }
+    }
+
+    /// Reads a raw, unconditioned entropy sample
+    #[inline]
+    pub fn sample(&mut self) -> Result<u16, Error> {
+        sample(seed::read, self.retries)
+    }
+
+    /// Fills `dest` with conditioned random bytes
+    #[inline]
+    pub fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        fill(seed::read, self.retries, dest)
+    }
+}
+
+impl Default for SeedRng {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl rand_core::RngCore for SeedRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entropy source is `DEAD` or does not produce samples.
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill(dest) {
+            panic!("entropy source error: {:?}", e);
+        }
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Ok(self.try_fill(dest)?)
+    }
+}
+
+impl rand_core::CryptoRng for SeedRng {}
+
+/// Polls `read` until it returns a sample, at most `retries` times after the first poll
+fn sample<F: FnMut() -> Seed>(mut read: F, retries: u32) -> Result<u16, Error> {
+    for _ in 0..=retries {
+        let seed = read();
+        match seed.opst() {
+            Opst::Es16 => return Ok(seed.bits() as u16),
+            Opst::Dead => return Err(Error::Dead),
+            Opst::Bist | Opst::Wait => core::hint::spin_loop(),
+        }
+    }
+    Err(Error::Timeout)
+}
+
+/// Fills `dest` with blocks of SHA-256 hashes of `SAMPLES_PER_BLOCK` samples
+fn fill<F: FnMut() -> Seed>(mut read: F, retries: u32, dest: &mut [u8]) -> Result<(), Error> {
+    for chunk in dest.chunks_mut(32) {
+        let mut hasher = Sha256::new();
+        for _ in 0..SAMPLES_PER_BLOCK {
+            hasher.update(sample(&mut read, retries)?.to_le_bytes());
+        }
+        chunk.copy_from_slice(&hasher.finalize()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fill, sample, Error};
+    use crate::register::seed::Seed;
+    use core::num::NonZeroU32;
+
+    const BIST: usize = 0b00 << 30;
+    const WAIT: usize = 0b01 << 30;
+    const ES16: usize = 0b10 << 30;
+    const DEAD: usize = 0b11 << 30;
+
+    #[test]
+    fn retries() {
+        let mut polls = [BIST, WAIT, WAIT, ES16 | 0x1234].into_iter();
+        let read = || Seed::from_bits(polls.next().unwrap());
+        assert_eq!(sample(read, 3), Ok(0x1234));
+
+        let mut polls = [WAIT, WAIT, ES16 | 0x1234].into_iter();
+        let read = || Seed::from_bits(polls.next().unwrap());
+        assert_eq!(sample(read, 1), Err(Error::Timeout));
+
+        let mut polls = [WAIT, DEAD].into_iter();
+        let read = || Seed::from_bits(polls.next().unwrap());
+        assert_eq!(sample(read, 10), Err(Error::Dead));
+    }
+
+    #[test]
+    fn conditioning() {
+        let mut count = 0;
+        let mut counter = || {
+            count += 1;
+            Seed::from_bits(ES16 | count)
+        };
+        let mut a = [0; 40];
+        fill(&mut counter, 0, &mut a).unwrap();
+        assert_eq!(count, 64);
+
+        // The same samples give the same output, whatever the length of the buffer
+        let mut count = 0;
+        let mut b = [0; 32];
+        fill(
+            || {
+                count += 1;
+                Seed::from_bits(ES16 | count)
+            },
+            0,
+            &mut b,
+        )
+        .unwrap();
+        assert_eq!(a[..32], b);
+        assert_ne!(a[..8], a[32..]);
+    }
+
+    #[test]
+    fn error_codes() {
+        for error in [Error::Dead, Error::Timeout] {
+            let code = rand_core::Error::from(error).code().unwrap();
+            assert_eq!(Error::from_code(code), Some(error));
+        }
+        assert_eq!(Error::from_code(NonZeroU32::new(1).unwrap()), None);
+    }
+}
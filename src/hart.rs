@@ -0,0 +1,255 @@
+//! Hart identification and hart-local storage
+//!
+//! Each hart records its `HartId` once at boot with `init`, either read from `mhartid` in M-mode
+//! or taken from the `a0` argument the SBI implementation passes to S-mode. The ID is kept in the
+//! `tp` register, or in `mscratch` (`sscratch` with the `s-mode` feature) when the
+//! `hart-local-scratch` feature is enabled, and `HartId::current` reads it back in a single
+//! instruction. Software using `tp` or the scratch register for another purpose (e.g. a trap
+//! handler swapping stacks with `mscratch`) must pick the other storage.
+//!
+//! `hart_local!` defines statics holding one value per hart, indexed by the ID of the current
+//! hart. The storage holds the ID itself rather than a pointer to a per-hart data block: reading
+//! the storage before `init` then yields a wrong ID, caught by the bounds check of `HartLocal`,
+//! where following an unset pointer would be undefined behavior and make `HartId::current`
+//! unsound. It also means that `init` needs no memory set aside for each hart, and each
+//! `HartLocal` is an array sized for its own data.
+//!
+//! `context::switch` leaves the storage to the hart, so a context resumed on another hart reads
+//! the ID of that hart. `HartLocal::get` requires `T: Sync`, as the value may then be accessed by
+//! an interrupt handler or, after a switch, by another hart; values that are not `Sync` (e.g. a
+//! `Cell`) are accessed with `HartLocal::borrow` inside a critical section.
+//!
+//! ```no_run
+//! use core::sync::atomic::{AtomicUsize, Ordering};
+//! use riscv::hart::{self, HartId};
+//!
+//! riscv::hart_local! {
+//!     static TICKS: [AtomicUsize; 4] = AtomicUsize::new(0);
+//! }
+//!
+//! // S-mode entry point, called with the arguments given by the SBI implementation
+//! extern "C" fn entry(hartid: usize, _dtb: usize) {
+//!     unsafe { hart::init(HartId::new(hartid)) };
+//!     TICKS.get().fetch_add(1, Ordering::Relaxed);
+//! }
+//! ```
+
+use crate::interrupt::CriticalSection;
+
+/// Hart identifier, as found in `mhartid`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct HartId(usize);
+
+impl HartId {
+    /// Wraps a raw hart ID
+    #[inline]
+    pub const fn new(id: usize) -> Self {
+        Self(id)
+    }
+
+    /// Raw hart ID
+    #[inline]
+    pub const fn id(self) -> usize {
+        self.0
+    }
+
+    /// Reads the ID of the current hart from `mhartid` (M-mode only)
+    #[inline]
+    pub fn from_mhartid() -> Self {
+        Self(crate::register::mhartid::read())
+    }
+
+    /// ID of the current hart, as recorded by `init`
+    #[inline]
+    pub fn current() -> Self {
+        Self(read_storage())
+    }
+}
+
+impl From<HartId> for usize {
+    #[inline]
+    fn from(hart: HartId) -> usize {
+        hart.0
+    }
+}
+
+/// Records the ID of the current hart
+///
+/// # Safety
+///
+/// Overwrites `tp`, or the scratch register with the `hart-local-scratch` feature. Must be called
+/// on each hart before it uses `HartId::current` or hart-local data, with its own ID.
+#[inline]
+pub unsafe fn init(hart: HartId) {
+    write_storage(hart.0);
+}
+
+/// Records the ID of the current hart, read from `mhartid` (M-mode only)
+///
+/// # Safety
+///
+/// See `init`.
+#[inline]
+pub unsafe fn init_from_mhartid() {
+    init(HartId::from_mhartid());
+}
+
+#[inline]
+fn read_storage() -> usize {
+    match () {
+        #[cfg(all(riscv, not(feature = "hart-local-scratch")))]
+        () => {
+            let r: usize;
+            unsafe {
+                core::arch::asm!("mv {0}, tp", out(reg) r, options(nomem, nostack, preserves_flags))
+            };
+            r
+        }
+        #[cfg(all(riscv, feature = "hart-local-scratch", not(feature = "s-mode")))]
+        () => crate::register::mscratch::read(),
+        #[cfg(all(riscv, feature = "hart-local-scratch", feature = "s-mode"))]
+        () => crate::register::sscratch::read(),
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+#[inline]
+#[allow(unused_variables)]
+unsafe fn write_storage(bits: usize) {
+    match () {
+        #[cfg(all(riscv, not(feature = "hart-local-scratch")))]
+        () => {
+            core::arch::asm!("mv tp, {0}", in(reg) bits, options(nomem, nostack, preserves_flags))
+        }
+        #[cfg(all(riscv, feature = "hart-local-scratch", not(feature = "s-mode")))]
+        () => crate::register::mscratch::write(bits),
+        #[cfg(all(riscv, feature = "hart-local-scratch", feature = "s-mode"))]
+        () => crate::register::sscratch::write(bits),
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// One value of type `T` for each of the harts `0` to `N - 1`
+///
+/// Usually defined with `hart_local!`. Without `T: Sync`, the value of the current hart is only
+/// accessed within a critical section, with `borrow`:
+///
+/// ```compile_fail
+/// use core::cell::Cell;
+///
+/// riscv::hart_local! {
+///     static COUNT: [Cell<usize>; 4] = Cell::new(0);
+/// }
+///
+/// COUNT.get().set(1);
+/// ```
+pub struct HartLocal<T, const N: usize> {
+    values: [T; N],
+}
+
+// Safety: without `T: Sync`, each hart only accesses its own value, with its interrupts disabled
+unsafe impl<T: Send, const N: usize> Sync for HartLocal<T, N> {}
+
+impl<T, const N: usize> HartLocal<T, N> {
+    /// Constructs the storage from the values of all the harts
+    #[inline]
+    pub const fn new(values: [T; N]) -> Self {
+        Self { values }
+    }
+
+    /// Value of the current hart, borrowed for the duration of the critical section
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID of the current hart is not lower than `N`.
+    #[inline]
+    pub fn borrow<'cs>(&'cs self, _cs: &'cs CriticalSection) -> &'cs T {
+        &self.values[HartId::current().0]
+    }
+}
+
+impl<T: Sync, const N: usize> HartLocal<T, N> {
+    /// Value of the current hart
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID of the current hart is not lower than `N`.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.values[HartId::current().0]
+    }
+
+    /// Value of `hart`, or `None` if its ID is not lower than `N`
+    #[inline]
+    pub fn get_for(&self, hart: HartId) -> Option<&T> {
+        self.values.get(hart.0)
+    }
+
+    /// Iterates over the values of all the harts
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.values.iter()
+    }
+}
+
+/// Defines statics holding one value per hart
+///
+/// `static NAME: [T; N] = INIT;` defines `NAME` as a `hart::HartLocal<T, N>` whose values are all
+/// initialized to the constant expression `INIT`.
+#[macro_export]
+macro_rules! hart_local {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: [$ty:ty; $harts:expr] = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::hart::HartLocal<$ty, { $harts }> = {
+                #[allow(clippy::declare_interior_mutable_const)]
+                const INIT: $ty = $init;
+                $crate::hart::HartLocal::new([INIT; $harts])
+            };
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HartId, HartLocal};
+    use core::cell::Cell;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    crate::hart_local! {
+        static COUNTERS: [AtomicUsize; 4] = AtomicUsize::new(0);
+    }
+
+    #[test]
+    fn get_for() {
+        COUNTERS
+            .get_for(HartId::new(2))
+            .unwrap()
+            .fetch_add(1, Ordering::Relaxed);
+        assert!(COUNTERS.get_for(HartId::new(4)).is_none());
+        let counts: [usize; 4] = [0, 1, 2, 3].map(|i| {
+            COUNTERS
+                .get_for(HartId::new(i))
+                .unwrap()
+                .load(Ordering::Relaxed)
+        });
+        assert_eq!(counts, [0, 0, 1, 0]);
+        assert_eq!(COUNTERS.iter().count(), 4);
+    }
+
+    #[test]
+    fn borrow() {
+        crate::hart_local! {
+            static CELLS: [Cell<usize>; 4] = Cell::new(0);
+        }
+
+        // Without `T: Sync`, the value of the current hart is only reachable through `borrow`
+        let borrow: for<'cs> fn(
+            &'cs HartLocal<Cell<usize>, 4>,
+            &'cs crate::interrupt::CriticalSection,
+        ) -> &'cs Cell<usize> = HartLocal::borrow;
+        let _ = (borrow, &CELLS);
+    }
+}
@@ -1,15 +1,19 @@
 //! Interrupts
+//!
+//! The interrupts of the current privilege mode are masked with `mstatus.MIE`, or with
+//! `sstatus.SIE` when the `s-mode` feature is enabled.
 
 // NOTE: Adapted from cortex-m/src/interrupt.rs
-use crate::register::mstatus;
 pub use bare_metal::{CriticalSection, Mutex};
 
 /// Disables all interrupts
 #[inline]
 pub unsafe fn disable() {
     match () {
-        #[cfg(riscv)]
-        () => mstatus::clear_mie(),
+        #[cfg(all(riscv, not(feature = "s-mode")))]
+        () => crate::register::mstatus::clear_mie(),
+        #[cfg(all(riscv, feature = "s-mode"))]
+        () => crate::register::sstatus::clear_sie(),
         #[cfg(not(riscv))]
         () => unimplemented!(),
     }
@@ -23,8 +27,26 @@ pub unsafe fn disable() {
 #[inline]
 pub unsafe fn enable() {
     match () {
-        #[cfg(riscv)]
-        () => mstatus::set_mie(),
+        #[cfg(all(riscv, not(feature = "s-mode")))]
+        () => crate::register::mstatus::set_mie(),
+        #[cfg(all(riscv, feature = "s-mode"))]
+        () => crate::register::sstatus::set_sie(),
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Disables all interrupts and returns whether they were enabled
+///
+/// The interrupt enable bit is read and cleared by a single `csrrci` instruction, so an interrupt
+/// handler cannot run between both and change it.
+#[inline]
+pub(crate) unsafe fn disable_and_save() -> bool {
+    match () {
+        #[cfg(all(riscv, not(feature = "s-mode")))]
+        () => crate::register::mstatus::read_clear_mie().mie(),
+        #[cfg(all(riscv, feature = "s-mode"))]
+        () => crate::register::sstatus::read_clear_sie().sie(),
         #[cfg(not(riscv))]
         () => unimplemented!(),
     }
@@ -38,18 +60,14 @@ pub fn free<F, R>(f: F) -> R
 where
     F: FnOnce(&CriticalSection) -> R,
 {
-    let mstatus = mstatus::read();
-
     // disable interrupts
-    unsafe {
-        disable();
-    }
+    let was_enabled = unsafe { disable_and_save() };
 
     let r = f(unsafe { &CriticalSection::new() });
 
     // If the interrupts were active before our `disable` call, then re-enable
     // them. Otherwise, keep them disabled
-    if mstatus.mie() {
+    if was_enabled {
         unsafe {
             enable();
         }
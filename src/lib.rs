@@ -2,7 +2,7 @@
 //!
 //! # Minimum Supported Rust Version (MSRV)
 //!
-//! This crate is guaranteed to compile on stable Rust 1.59 and up. It *might*
+//! This crate is guaranteed to compile on stable Rust 1.60 and up. It *might*
 //! compile with older versions but that may change in any new patch release.
 //!
 //! # Features
@@ -12,14 +12,55 @@
 //! - Access to core registers like `mstatus` or `mcause`.
 //! - Interrupt manipulation mechanisms.
 //! - Wrappers around assembly instructions like `WFI`.
+//! - Bindings to the RISC-V Supervisor Binary Interface (SBI).
+//!
+//! # Optional features
+//!
+//! ## `s-mode`
+//!
+//! The crate runs in S-mode: `interrupt` masks the supervisor interrupts (`sstatus.SIE`) instead
+//! of the machine interrupts (`mstatus.MIE`).
+//!
+//! ## `hart-local-scratch`
+//!
+//! `hart` keeps the ID of the current hart in `mscratch` (`sscratch` with `s-mode`) instead of
+//! `tp`, which `context::switch` then saves and restores with the other registers.
+//!
+//! ## `context-gp`
+//!
+//! `context::switch` also saves and restores the global pointer (`gp`).
+//!
+//! ## `entropy`
+//!
+//! Provides `entropy::SeedRng`, a `rand_core` random number generator conditioning the samples of
+//! the Zkr entropy source (`seed` CSR).
+//!
+//! ## `critical-section-single-hart`
+//!
+//! Provides an implementation of the `critical-section` crate for single hart systems, disabling
+//! the interrupts of the current privilege mode.
 
 #![no_std]
 
 pub mod asm;
+pub mod cache;
+pub mod code_patch;
+pub mod context;
+#[cfg(feature = "critical-section-single-hart")]
+mod critical_section;
+pub mod decode;
 pub mod delay;
+#[cfg(feature = "entropy")]
+pub mod entropy;
+pub mod hart;
 pub mod interrupt;
+pub mod misaligned;
 pub mod peripheral;
 pub mod register;
+pub mod sbi;
+#[cfg(target_has_atomic = "ptr")]
+pub mod sync;
+pub mod timer;
 
 #[macro_use]
 mod macros;
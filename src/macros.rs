@@ -52,3 +52,232 @@ macro_rules! singleton {
         })
     };
 }
+
+/// Macro to create a mutable reference to a statically allocated value, on multi-hart systems
+///
+/// Like `singleton!`, but the first use is claimed with an atomic swap of a flag instead of a
+/// critical section, so that the macro is sound when several harts run it concurrently. Exactly
+/// one of them gets `Some`. Only available on targets implementing the A extension.
+///
+/// # Example
+///
+/// ``` no_run
+/// use riscv::atomic_singleton;
+///
+/// fn init() -> &'static mut [u8; 64] {
+///     // Panics if `init` is called twice, by any hart
+///     atomic_singleton!(: [u8; 64] = [0; 64]).unwrap()
+/// }
+/// ```
+#[cfg(target_has_atomic = "8")]
+#[macro_export]
+macro_rules! atomic_singleton {
+    (: $ty:ty = $expr:expr) => {{
+        static TAKEN: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+        static mut VAR: core::mem::MaybeUninit<$ty> = core::mem::MaybeUninit::uninit();
+
+        if TAKEN.swap(true, core::sync::atomic::Ordering::Acquire) {
+            None
+        } else {
+            let expr = $expr;
+
+            // Safety: only the caller that flipped `TAKEN` reaches this point
+            #[allow(unsafe_code)]
+            let var: &'static mut $ty = unsafe { (*core::ptr::addr_of_mut!(VAR)).write(expr) };
+            Some(var)
+        }
+    }};
+}
+
+/// `VSETVLI` instruction wrapper
+///
+/// `vsetvli!(avl, sew, lmul, ta|tu, ma|mu)` sets `vtype` to the element width `sew` (a
+/// [`Sew`](crate::register::vtype::Sew)), the group multiplier `lmul` (an
+/// [`Lmul`](crate::register::vtype::Lmul)) and the tail and mask policies, and requests `avl`
+/// elements, evaluating to the number of elements `vl` the following vector instructions will
+/// process. `sew` and `lmul` select the immediate encoding of the instruction through a `match`,
+/// which is resolved at compile time when they are constants. Must be used in an `unsafe` block:
+/// see [`asm::vsetvl`](crate::asm::vsetvl).
+///
+/// # Example
+///
+/// ``` no_run
+/// use riscv::register::vtype::{Lmul, Sew};
+///
+/// let vl: usize = unsafe { riscv::vsetvli!(100, Sew::E32, Lmul::M2, ta, ma) };
+/// ```
+#[cfg(all(riscv, target_feature = "v"))]
+#[macro_export]
+macro_rules! vsetvli {
+    (@policy [$($insn:tt)*] ta ma, $($rest:tt)*) => {
+        $crate::vsetvli!(@match [$($insn)*] "ta, ma", $($rest)*)
+    };
+    (@policy [$($insn:tt)*] ta mu, $($rest:tt)*) => {
+        $crate::vsetvli!(@match [$($insn)*] "ta, mu", $($rest)*)
+    };
+    (@policy [$($insn:tt)*] tu ma, $($rest:tt)*) => {
+        $crate::vsetvli!(@match [$($insn)*] "tu, ma", $($rest)*)
+    };
+    (@policy [$($insn:tt)*] tu mu, $($rest:tt)*) => {
+        $crate::vsetvli!(@match [$($insn)*] "tu, mu", $($rest)*)
+    };
+    (@policy [$($insn:tt)*] $($rest:tt)*) => {
+        compile_error!("expected `ta` or `tu`, followed by `ma` or `mu`")
+    };
+    (@match [$($insn:tt)*] $policy:literal, $sew:expr, $lmul:expr, $($operands:tt)*) => {{
+        let sew: $crate::register::vtype::Sew = $sew;
+        let lmul: $crate::register::vtype::Lmul = $lmul;
+        {
+            use $crate::register::vtype::{Lmul, Sew};
+            match (sew, lmul) {
+                (Sew::E8, Lmul::Mf8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, mf8, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::Mf4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, mf4, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::Mf2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, mf2, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::M1) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, m1, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::M2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, m2, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::M4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, m4, ", $policy,] $($operands)*)
+                }
+                (Sew::E8, Lmul::M8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e8, m8, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::Mf8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, mf8, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::Mf4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, mf4, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::Mf2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, mf2, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::M1) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, m1, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::M2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, m2, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::M4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, m4, ", $policy,] $($operands)*)
+                }
+                (Sew::E16, Lmul::M8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e16, m8, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::Mf8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, mf8, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::Mf4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, mf4, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::Mf2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, mf2, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::M1) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, m1, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::M2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, m2, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::M4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, m4, ", $policy,] $($operands)*)
+                }
+                (Sew::E32, Lmul::M8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e32, m8, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::Mf8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, mf8, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::Mf4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, mf4, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::Mf2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, mf2, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::M1) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, m1, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::M2) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, m2, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::M4) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, m4, ", $policy,] $($operands)*)
+                }
+                (Sew::E64, Lmul::M8) => {
+                    $crate::vsetvli!(@asm [$($insn)* "e64, m8, ", $policy,] $($operands)*)
+                }
+            }
+        }
+    }};
+    (@asm [$($insn:tt)*] $($operands:tt)*) => {{
+        let vl: usize;
+        core::arch::asm!(
+            concat!($($insn)*),
+            out(reg) vl,
+            $($operands)*
+            options(nomem, nostack),
+        );
+        vl
+    }};
+    ($avl:expr, $sew:expr, $lmul:expr, $ta:ident, $ma:ident) => {{
+        let avl: usize = $avl;
+        $crate::vsetvli!(@policy ["vsetvli {0}, {1}, ",] $ta $ma, $sew, $lmul, in(reg) avl,)
+    }};
+}
+
+/// `VSETIVLI` instruction wrapper
+///
+/// Same as `vsetvli!`, with `avl` given as an integer literal from 0 to 31.
+///
+/// # Example
+///
+/// ``` no_run
+/// use riscv::register::vtype::{Lmul, Sew};
+///
+/// let vl: usize = unsafe { riscv::vsetivli!(16, Sew::E8, Lmul::M1, tu, mu) };
+/// ```
+#[cfg(all(riscv, target_feature = "v"))]
+#[macro_export]
+macro_rules! vsetivli {
+    ($avl:literal, $sew:expr, $lmul:expr, $ta:ident, $ma:ident) => {
+        $crate::vsetvli!(
+            @policy ["vsetivli {0}, ", stringify!($avl), ", ",] $ta $ma, $sew, $lmul,
+        )
+    };
+}
+
+/// Prints to the SBI debug console
+///
+/// Equivalent to `print!`, but writes through [`sbi::dbcn::Console`](crate::sbi::dbcn::Console).
+/// Usable from S-mode before any console driver is initialized.
+#[macro_export]
+macro_rules! sbi_print {
+    ($($arg:tt)*) => {
+        $crate::sbi::dbcn::_print(format_args!($($arg)*))
+    };
+}
+
+/// Prints to the SBI debug console, with a newline
+///
+/// Equivalent to `println!`, but writes through
+/// [`sbi::dbcn::Console`](crate::sbi::dbcn::Console).
+#[macro_export]
+macro_rules! sbi_println {
+    () => {
+        $crate::sbi_print!("\n")
+    };
+    ($fmt:expr) => {
+        $crate::sbi_print!(concat!($fmt, "\n"))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        $crate::sbi_print!(concat!($fmt, "\n"), $($arg)*)
+    };
+}
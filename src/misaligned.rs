@@ -0,0 +1,570 @@
+//! Emulation of misaligned loads and stores
+//!
+//! Harts that do not support misaligned accesses raise a load or store address-misaligned
+//! exception, which M-mode software is expected to emulate for the lower privilege modes. `handle`
+//! fetches and decodes the instruction at `mepc`, performs the access one byte at a time with
+//! `mstatus.MPRV` set (so that the translation and protection of the trapped privilege mode in
+//! `mstatus.MPP` apply), updates the saved registers and skips the instruction.
+//!
+//! The integer, compressed and floating-point loads and stores are supported. Like the integer
+//! registers, the floating-point registers are taken from the state saved by the trap handler,
+//! e.g. the `f` field of a [`context::FpState`](crate::context::FpState) restored with
+//! `_riscv_fp_restore` when returning from the trap: the live registers may be used by the
+//! compiled code of the handler.
+//!
+//! Each access runs with a temporary trap vector and the interrupts masked, like the probes of
+//! [`register::probe`](crate::register::probe). A page or access fault raised by the fetch or by
+//! the emulated access is caught: `mepc`, `mcause`, `mtval` and `mstatus` are restored, and
+//! `handle` returns `Error::AccessFault`, which the trap handler must forward to the trapped
+//! privilege mode. A fault of the fetch is reported as the instruction access or page fault the
+//! trapped code would have raised.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use riscv::context::FpState;
+//! use riscv::misaligned;
+//! use riscv::register::mcause::{self, Exception, Trap};
+//!
+//! fn on_exception(regs: &mut [usize; 32], fp: &mut FpState) {
+//!     match mcause::read().cause() {
+//!         Trap::Exception(Exception::LoadMisaligned | Exception::StoreMisaligned) => {
+//!             if unsafe { misaligned::handle(regs, Some(&mut fp.f)) }.is_err() {
+//!                 // Forward the exception to S-mode
+//!             }
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::decode::{self, Instruction, LoadWidth, Precision, Reg, Xlen};
+use crate::register::mcause::{self, Exception, Trap};
+use crate::register::mepc;
+
+/// `mstatus.MPRV`
+#[cfg(riscv)]
+const MPRV: usize = 1 << 17;
+/// `mstatus.MXR`
+#[cfg(riscv)]
+const MXR: usize = 1 << 19;
+
+/// Emulation error
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// `mcause` is not a load or store address-misaligned exception
+    NotMisaligned,
+    /// The instruction at `mepc` could not be decoded
+    IllegalInstruction(u32),
+    /// The instruction is not a load or store that can be emulated (atomic memory operation,
+    /// floating-point access without floating-point registers, or an access that does not match
+    /// `mcause`)
+    Unsupported(Instruction),
+    /// Fetching the instruction or accessing its operand raised the exception `cause`, with
+    /// `mtval` set to `tval`
+    AccessFault {
+        /// Cause of the exception
+        cause: Exception,
+        /// Faulting address
+        tval: usize,
+    },
+}
+
+/// Emulates the misaligned load or store that raised the current exception
+///
+/// `regs` and `fregs` hold the integer and floating-point registers of the trapped code, indexed
+/// by register number, with the single-precision values NaN-boxed; without `fregs`, the
+/// floating-point accesses are `Unsupported`. On success, the destination register of a load is
+/// updated and `mepc` points to the next instruction. No register is modified on error, but a
+/// store that faults may have written some of its bytes.
+///
+/// # Safety
+///
+/// Must be called from the M-mode trap handler of a load or store address-misaligned exception,
+/// with `mepc` and `mstatus.MPP` still holding the state of the trapped code.
+pub unsafe fn handle(regs: &mut [usize; 32], fregs: Option<&mut [u64; 32]>) -> Result<(), Error> {
+    let load = match mcause::read().cause() {
+        Trap::Exception(Exception::LoadMisaligned) => true,
+        Trap::Exception(Exception::StoreMisaligned) => false,
+        _ => return Err(Error::NotMisaligned),
+    };
+    let pc = mepc::read();
+    let (bits, len) = fetch(pc)?;
+    let insn = decode::decode(bits, Xlen::native()).ok_or(Error::IllegalInstruction(bits))?;
+    if is_load(&insn) != Some(load) {
+        return Err(Error::Unsupported(insn));
+    }
+    emulate(insn, regs, fregs, &mut Trapped)?;
+    mepc::write(pc.wrapping_add(len));
+    Ok(())
+}
+
+/// Whether `insn` is a load (`Some(true)`) or a store (`Some(false)`) that can be emulated
+fn is_load(insn: &Instruction) -> Option<bool> {
+    match insn {
+        Instruction::Load { .. } | Instruction::FpLoad { .. } => Some(true),
+        Instruction::Store { .. } | Instruction::FpStore { .. } => Some(false),
+        _ => None,
+    }
+}
+
+/// Memory seen by the emulated instruction
+trait Target {
+    /// Loads the byte at `addr`
+    unsafe fn load(&mut self, addr: usize) -> Result<u8, Error>;
+    /// Stores `byte` at `addr`
+    unsafe fn store(&mut self, addr: usize, byte: u8) -> Result<(), Error>;
+}
+
+/// Reads register `reg`, `x0` being zero
+#[inline]
+fn read_reg(regs: &[usize; 32], reg: Reg) -> usize {
+    if reg == Reg::ZERO {
+        0
+    } else {
+        regs[reg.index()]
+    }
+}
+
+/// Address accessed by a load or store
+#[inline]
+fn address(regs: &[usize; 32], rs1: Reg, offset: i32) -> usize {
+    read_reg(regs, rs1).wrapping_add(offset as isize as usize)
+}
+
+/// Loads `size` bytes at `addr`, in little-endian order
+unsafe fn load<T: Target>(target: &mut T, addr: usize, size: usize) -> Result<u64, Error> {
+    let mut value = 0;
+    for i in 0..size {
+        value |= u64::from(target.load(addr.wrapping_add(i))?) << (8 * i);
+    }
+    Ok(value)
+}
+
+/// Stores the `size` lowest bytes of `value` at `addr`, in little-endian order
+unsafe fn store<T: Target>(
+    target: &mut T,
+    addr: usize,
+    size: usize,
+    value: u64,
+) -> Result<(), Error> {
+    for i in 0..size {
+        target.store(addr.wrapping_add(i), (value >> (8 * i)) as u8)?;
+    }
+    Ok(())
+}
+
+/// Sign or zero-extends the `size` lowest bytes of `value`
+#[inline]
+fn extend(value: u64, width: LoadWidth) -> usize {
+    let shift = 64 - 8 * width.size() as u32;
+    if width.is_signed() {
+        (((value << shift) as i64) >> shift) as usize
+    } else {
+        ((value << shift) >> shift) as usize
+    }
+}
+
+/// Performs the load or store `insn` one byte at a time
+unsafe fn emulate<T: Target>(
+    insn: Instruction,
+    regs: &mut [usize; 32],
+    fregs: Option<&mut [u64; 32]>,
+    target: &mut T,
+) -> Result<(), Error> {
+    match insn {
+        Instruction::Load {
+            width,
+            rd,
+            rs1,
+            offset,
+        } => {
+            let value = load(target, address(regs, rs1, offset), width.size())?;
+            if rd != Reg::ZERO {
+                regs[rd.index()] = extend(value, width);
+            }
+        }
+        Instruction::Store {
+            width,
+            rs1,
+            rs2,
+            offset,
+        } => {
+            let value = read_reg(regs, rs2) as u64;
+            store(target, address(regs, rs1, offset), width.size(), value)?;
+        }
+        Instruction::FpLoad {
+            precision,
+            rd,
+            rs1,
+            offset,
+        } => {
+            let fregs = fregs.ok_or(Error::Unsupported(insn))?;
+            let value = load(target, address(regs, rs1, offset), precision.size())?;
+            fregs[rd.index()] = match precision {
+                Precision::Single => value | 0xffff_ffff_0000_0000,
+                Precision::Double => value,
+            };
+        }
+        Instruction::FpStore {
+            precision,
+            rs1,
+            rs2,
+            offset,
+        } => {
+            let fregs = fregs.ok_or(Error::Unsupported(insn))?;
+            let value = fregs[rs2.index()];
+            store(target, address(regs, rs1, offset), precision.size(), value)?;
+        }
+        _ => return Err(Error::Unsupported(insn)),
+    }
+    Ok(())
+}
+
+/// Fetches the instruction at `pc` in the trapped privilege mode, returning its encoding and
+/// length
+unsafe fn fetch(pc: usize) -> Result<(u32, usize), Error> {
+    let low = fetch_parcel(pc).map_err(fetch_fault)?;
+    Ok(match decode::instruction_len(low) {
+        Some(2) => (u32::from(low), 2),
+        _ => {
+            let high = fetch_parcel(pc.wrapping_add(2)).map_err(fetch_fault)?;
+            (u32::from(low) | u32::from(high) << 16, 4)
+        }
+    })
+}
+
+/// Turns the load fault raised by `fetch_parcel` into the instruction fault the trapped code would
+/// have raised
+fn fetch_fault(error: Error) -> Error {
+    match error {
+        Error::AccessFault { cause, tval } => Error::AccessFault {
+            cause: match cause {
+                Exception::LoadFault => Exception::InstructionFault,
+                Exception::LoadPageFault => Exception::InstructionPageFault,
+                cause => cause,
+            },
+            tval,
+        },
+        error => error,
+    }
+}
+
+/// Runs `access`, which returns `false` if the trap vector caught an exception, with the
+/// interrupts masked, and restores the trap CSRs overwritten by the exception
+///
+/// Same as `register::probe::catch`, with the M-mode CSRs even with the `s-mode` feature, and
+/// returning the cause and address of the exception.
+#[cfg(riscv)]
+#[inline]
+unsafe fn catch(access: impl FnOnce() -> bool) -> Result<(), Error> {
+    let (status, epc, cause, tval): (usize, usize, usize, usize);
+    core::arch::asm!(
+        "csrrci {status}, mstatus, 8",
+        "csrr {epc}, mepc",
+        "csrr {cause}, mcause",
+        "csrr {tval}, mtval",
+        status = out(reg) status,
+        epc = out(reg) epc,
+        cause = out(reg) cause,
+        tval = out(reg) tval,
+    );
+    let result = if access() {
+        Ok(())
+    } else {
+        let fault = Error::AccessFault {
+            cause: Exception::from(mcause::read().code()),
+            tval: crate::register::mtval::read(),
+        };
+        core::arch::asm!(
+            "csrw mepc, {epc}",
+            "csrw mcause, {cause}",
+            "csrw mtval, {tval}",
+            epc = in(reg) epc,
+            cause = in(reg) cause,
+            tval = in(reg) tval,
+        );
+        Err(fault)
+    };
+    core::arch::asm!("csrw mstatus, {0}", in(reg) status);
+    result
+}
+
+/// Executes the load or store `$insn` with the bits `{mask}` set in `mstatus` and a temporary
+/// trap vector pointing past it, catching its exception
+#[cfg(riscv)]
+macro_rules! mprv_access {
+    ($insn:literal, $($operands:tt)*) => {
+        catch(|| {
+            let ok: usize;
+            core::arch::asm!(
+                "li {ok}, 0",
+                "lla {tvec}, 1f",
+                "csrrw {tvec}, mtvec, {tvec}",
+                "csrrs {status}, mstatus, {mask}",
+                $insn,
+                "li {ok}, 1",
+                ".p2align 2",
+                "1:",
+                "csrw mstatus, {status}",
+                "csrw mtvec, {tvec}",
+                ok = out(reg) ok,
+                tvec = out(reg) _,
+                status = out(reg) _,
+                $($operands)*
+                options(nostack),
+            );
+            ok != 0
+        })
+    };
+}
+
+/// Loads the 16-bit parcel at `pc` with `mstatus.MPRV` and `mstatus.MXR` set
+#[inline]
+unsafe fn fetch_parcel(pc: usize) -> Result<u16, Error> {
+    match () {
+        #[cfg(riscv)]
+        () => {
+            let mut parcel: usize = 0;
+            mprv_access!(
+                "lhu {parcel}, 0({pc})",
+                mask = in(reg) MPRV | MXR,
+                pc = in(reg) pc,
+                parcel = out(reg) parcel,
+            )?;
+            Ok(parcel as u16)
+        }
+        #[cfg(not(riscv))]
+        () => {
+            let _ = pc;
+            unimplemented!()
+        }
+    }
+}
+
+/// Memory of the trapped privilege mode
+struct Trapped;
+
+impl Target for Trapped {
+    #[inline]
+    unsafe fn load(&mut self, addr: usize) -> Result<u8, Error> {
+        match () {
+            #[cfg(riscv)]
+            () => {
+                let mut byte: usize = 0;
+                mprv_access!(
+                    "lbu {byte}, 0({addr})",
+                    mask = in(reg) MPRV,
+                    addr = in(reg) addr,
+                    byte = out(reg) byte,
+                )?;
+                Ok(byte as u8)
+            }
+            #[cfg(not(riscv))]
+            () => {
+                let _ = addr;
+                unimplemented!()
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn store(&mut self, addr: usize, byte: u8) -> Result<(), Error> {
+        match () {
+            #[cfg(riscv)]
+            () => mprv_access!(
+                "sb {byte}, 0({addr})",
+                mask = in(reg) MPRV,
+                addr = in(reg) addr,
+                byte = in(reg) byte as usize,
+            ),
+            #[cfg(not(riscv))]
+            () => {
+                let _ = (addr, byte);
+                unimplemented!()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{emulate, fetch_fault, is_load, Error, Target};
+    use crate::decode::{self, Instruction, Xlen};
+    use crate::register::mcause::Exception;
+
+    /// Memory at address `0x1000` and saved floating-point registers
+    struct Fake {
+        memory: [u8; 32],
+        fregs: [u64; 32],
+        fp: bool,
+    }
+
+    struct Memory<'a>(&'a mut [u8; 32]);
+
+    impl Memory<'_> {
+        fn byte(&mut self, addr: usize, cause: Exception) -> Result<&mut u8, Error> {
+            addr.checked_sub(0x1000)
+                .and_then(|offset| self.0.get_mut(offset))
+                .ok_or(Error::AccessFault { cause, tval: addr })
+        }
+    }
+
+    impl Target for Memory<'_> {
+        unsafe fn load(&mut self, addr: usize) -> Result<u8, Error> {
+            self.byte(addr, Exception::LoadFault).map(|byte| *byte)
+        }
+
+        unsafe fn store(&mut self, addr: usize, byte: u8) -> Result<(), Error> {
+            *self.byte(addr, Exception::StoreFault)? = byte;
+            Ok(())
+        }
+    }
+
+    fn run(bits: u32, regs: &mut [usize; 32], fake: &mut Fake) -> Result<(), Error> {
+        let insn = decode::decode(bits, Xlen::native()).unwrap();
+        let fregs = if fake.fp { Some(&mut fake.fregs) } else { None };
+        unsafe { emulate(insn, regs, fregs, &mut Memory(&mut fake.memory)) }
+    }
+
+    fn fake() -> Fake {
+        let mut memory = [0; 32];
+        for (i, byte) in memory.iter_mut().enumerate() {
+            *byte = 0xe0 + i as u8;
+        }
+        Fake {
+            memory,
+            fregs: [0; 32],
+            fp: true,
+        }
+    }
+
+    #[test]
+    fn integer() {
+        let mut fake = fake();
+        let mut regs = [0; 32];
+        regs[11] = 0x1001;
+
+        // lh a0, 2(a1) and lhu a0, 2(a1)
+        run(0x00259503, &mut regs, &mut fake).unwrap();
+        assert_eq!(regs[10], 0xffff_e4e3_usize as i32 as usize);
+        run(0x0025d503, &mut regs, &mut fake).unwrap();
+        assert_eq!(regs[10], 0xe4e3);
+        // c.lw a0, 4(a1)
+        run(0x41c8, &mut regs, &mut fake).unwrap();
+        assert_eq!(regs[10], 0xe8e7_e6e5_u32 as i32 as usize);
+        // lw zero, 0(a1)
+        run(0x0005a003, &mut regs, &mut fake).unwrap();
+        assert_eq!(regs[0], 0);
+
+        // sw a2, -1(a1) and sw zero, 4(a1)
+        regs[12] = 0x1234_5678;
+        run(0xfec5afa3, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.memory[..5], [0x78, 0x56, 0x34, 0x12, 0xe4]);
+        run(0x0005a223, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.memory[5..10], [0, 0, 0, 0, 0xe9]);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn doubleword() {
+        let mut fake = fake();
+        let mut regs = [0; 32];
+        regs[11] = 0x1003;
+
+        // ld a0, 0(a1) and sd a0, 8(a1)
+        run(0x0005b503, &mut regs, &mut fake).unwrap();
+        assert_eq!(regs[10], 0xeae9_e8e7_e6e5_e4e3);
+        run(0x00a5b423, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.memory[11..19], fake.memory[3..11]);
+    }
+
+    #[test]
+    fn floating_point() {
+        let mut fake = fake();
+        let mut regs = [0; 32];
+        regs[11] = 0x1001;
+
+        // flw fa0, 0(a1) and fld fa1, 1(a1)
+        run(0x0005a507, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.fregs[10], 0xffff_ffff_e4e3_e2e1);
+        run(0x0015b587, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.fregs[11], 0xe9e8_e7e6_e5e4_e3e2);
+
+        // fsw fa0, 8(a1) and fsd fa1, 16(a1)
+        run(0x00a5a427, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.memory[9..13], [0xe1, 0xe2, 0xe3, 0xe4]);
+        run(0x00b5b827, &mut regs, &mut fake).unwrap();
+        assert_eq!(
+            fake.memory[17..25],
+            [0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9]
+        );
+
+        // fld fs2, 0(a1) and c.fld fs0, 0(a1), into callee-saved registers
+        run(0x0005b907, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.fregs[18], 0xe8e7_e6e5_e4e3_e2e1);
+        run(0x2180, &mut regs, &mut fake).unwrap();
+        assert_eq!(fake.fregs[8], 0xe8e7_e6e5_e4e3_e2e1);
+
+        // fld without floating-point registers
+        fake.fp = false;
+        assert!(matches!(
+            run(0x0015b587, &mut regs, &mut fake),
+            Err(Error::Unsupported(Instruction::FpLoad { .. }))
+        ));
+    }
+
+    #[test]
+    fn fault() {
+        let mut fake = fake();
+        let mut regs = [0; 32];
+        regs[11] = 0x101e;
+        regs[12] = 0x1234_5678;
+
+        // lw a0, 0(a1) and sw a2, 0(a1) across the end of the memory
+        assert_eq!(
+            run(0x0005a503, &mut regs, &mut fake),
+            Err(Error::AccessFault {
+                cause: Exception::LoadFault,
+                tval: 0x1020
+            })
+        );
+        assert_eq!(regs[10], 0);
+        assert_eq!(
+            run(0x00c5a023, &mut regs, &mut fake),
+            Err(Error::AccessFault {
+                cause: Exception::StoreFault,
+                tval: 0x1020
+            })
+        );
+        assert_eq!(fake.memory[30..], [0x78, 0x56]);
+
+        // A fault of the fetch is an instruction fault
+        assert_eq!(
+            fetch_fault(Error::AccessFault {
+                cause: Exception::LoadPageFault,
+                tval: 0x1000
+            }),
+            Error::AccessFault {
+                cause: Exception::InstructionPageFault,
+                tval: 0x1000
+            }
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut fake = fake();
+        let mut regs = [0; 32];
+
+        // amoadd.w a0, a2, (a1) and addi a0, a0, 1
+        for bits in [0x00c5a52f, 0x00150513] {
+            let insn = decode::decode(bits, Xlen::native()).unwrap();
+            assert_eq!(is_load(&insn), None);
+            assert_eq!(
+                run(bits, &mut regs, &mut fake),
+                Err(Error::Unsupported(insn))
+            );
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Advanced Core Local Interruptor (ACLINT)
+//!
+//! Ref: [RISC-V Advanced Core Local Interruptor Specification](https://github.com/riscv/riscv-aclint/releases)
+
+use volatile_register::RW;
+
+/// Number of `mtimecmp` registers of an MTIMER device
+pub const MAX_HARTS: usize = 4095;
+
+/// MTIMER register block
+///
+/// Laid out like the timer part of the SiFive CLINT, with the `mtime` register following the
+/// `mtimecmp` registers. The 64-bit registers are accessed as two 32-bit halves so that the access
+/// order is the same on RV32 and RV64.
+#[repr(C)]
+pub struct Mtimer {
+    /// 0x0000 8 bytes mtimecmp[0]
+    /// 0x0008 8 bytes mtimecmp[1]
+    /// ...
+    /// 0x7FF0 8 bytes mtimecmp[4094]
+    mtimecmp: [[RW<u32>; 2]; MAX_HARTS],
+    /// 0x7FF8 8 bytes mtime
+    mtime: [RW<u32>; 2],
+}
+
+impl Mtimer {
+    /// Reads `mtime`
+    #[inline]
+    pub fn mtime(&self) -> u64 {
+        loop {
+            let hi = self.mtime[1].read();
+            let lo = self.mtime[0].read();
+            if hi == self.mtime[1].read() {
+                return (hi as u64) << 32 | lo as u64;
+            }
+        }
+    }
+
+    /// Reads the `mtimecmp` register of `hart`
+    #[inline]
+    pub fn mtimecmp(&self, hart: usize) -> u64 {
+        assert!(hart < MAX_HARTS);
+        let mtimecmp = &self.mtimecmp[hart];
+        (mtimecmp[1].read() as u64) << 32 | mtimecmp[0].read() as u64
+    }
+
+    /// Writes the `mtimecmp` register of `hart`
+    ///
+    /// The low half is first set to all ones, so that no intermediate value of the register is
+    /// lower than both the old and the new value and no spurious interrupt is raised.
+    #[inline]
+    pub fn set_mtimecmp(&self, hart: usize, value: u64) {
+        assert!(hart < MAX_HARTS);
+        let mtimecmp = &self.mtimecmp[hart];
+
+        // Safety: Writes to the MMIO region
+        unsafe {
+            mtimecmp[0].write(u32::MAX);
+            mtimecmp[1].write((value >> 32) as u32);
+            mtimecmp[0].write(value as u32);
+        }
+    }
+
+    /// Writes `mtime`
+    ///
+    /// # Safety
+    ///
+    /// `mtime` is shared by all the harts; changing it affects every timer deadline in the system.
+    #[inline]
+    pub unsafe fn set_mtime(&self, value: u64) {
+        self.mtime[0].write(0);
+        self.mtime[1].write((value >> 32) as u32);
+        self.mtime[0].write(value as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mtimer;
+    use core::mem::size_of;
+    use memoffset::offset_of;
+
+    #[test]
+    fn sizeof_register_block() {
+        assert_eq!(size_of::<Mtimer>(), 0x8000)
+    }
+
+    #[test]
+    fn offset_of() {
+        assert_eq!(0x0000, offset_of!(Mtimer, mtimecmp));
+        assert_eq!(0x7FF8, offset_of!(Mtimer, mtime));
+    }
+}
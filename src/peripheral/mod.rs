@@ -1,3 +1,4 @@
 //! RISC-V peripherals
 
+pub mod aclint;
 pub mod aplic;
@@ -0,0 +1,178 @@
+//! Access to a CSR selected at run time
+//!
+//! The CSR number is an immediate of the `csrr*` instructions, so `read_dynamic` and
+//! `write_dynamic` jump into a generated table of 4096 instructions, one per CSR number (32 KiB of
+//! code each). The exception raised by an unimplemented CSR is caught as in
+//! [`probe`](super::probe).
+//!
+//! The status and trap vector CSRs, which the probe modifies, are accessed directly instead.
+//! Without the `s-mode` feature, `sstatus` is read through the probe, as an M-mode-only hart does
+//! not implement it, and only written directly once the probe found it: `read_dynamic(0x100)` and
+//! `write_dynamic(0x100, _)` then return `CsrError::Unimplemented` on such a hart.
+
+/// Error of a dynamic CSR access
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CsrError {
+    /// The number is not a 12-bit CSR number
+    OutOfRange,
+    /// The access raised an exception: the CSR is not implemented, not accessible from the
+    /// current privilege mode, or read-only
+    Unimplemented,
+}
+
+/// Largest CSR number
+const MAX_CSR: u16 = 0xFFF;
+
+/// Jumps to entry `{offset} / 8` of a table of the instructions `$encoding | csr << 20`, each
+/// followed by a jump to the end of the table
+#[cfg(riscv)]
+macro_rules! csr_table {
+    ($encoding:literal) => {
+        concat!(
+            "lla {tmp}, 2f\n",
+            "add {tmp}, {tmp}, {offset}\n",
+            "jr {tmp}\n",
+            ".option push\n",
+            ".option norvc\n",
+            "2:\n",
+            ".set csr_number, 0\n",
+            ".rept 4096\n",
+            ".4byte (csr_number << 20) | ",
+            $encoding,
+            "\n",
+            "j 3f\n",
+            ".set csr_number, csr_number + 1\n",
+            ".endr\n",
+            ".option pop\n",
+            "3:",
+        )
+    };
+}
+
+/// Reads CSR `num`
+///
+/// Returns an error if `num` is larger than `0xFFF`, or if the CSR is not implemented, which
+/// includes `sstatus` on a hart without S-mode.
+#[inline(never)]
+pub fn read_dynamic(num: u16) -> Result<usize, CsrError> {
+    if num > MAX_CSR {
+        return Err(CsrError::OutOfRange);
+    }
+    match () {
+        #[cfg(riscv)]
+        () => unsafe {
+            if let Some(bits) = read_direct(num) {
+                return Ok(bits);
+            }
+            let mut bits: usize = 0;
+            // csrrs a0, csr, x0
+            let ok = probe_csr_instruction!(
+                csr_table!("0x2573"),
+                offset = in(reg) usize::from(num) * 8,
+                tmp = out(reg) _,
+                inout("a0") bits,
+            );
+            if ok {
+                Ok(bits)
+            } else {
+                Err(CsrError::Unimplemented)
+            }
+        },
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Writes `bits` to CSR `num`
+///
+/// Returns an error if `num` is larger than `0xFFF`, or if the CSR is not implemented or
+/// read-only.
+///
+/// # Safety
+///
+/// Changing the CSR may break the assumptions of the running code.
+#[inline(never)]
+pub unsafe fn write_dynamic(num: u16, bits: usize) -> Result<(), CsrError> {
+    if num > MAX_CSR {
+        return Err(CsrError::OutOfRange);
+    }
+    match () {
+        #[cfg(riscv)]
+        () => {
+            // `catch` restores `mstatus`, which would undo a write to `sstatus` through the probe
+            if cfg!(not(feature = "s-mode")) && num == 0x100 {
+                read_dynamic(num)?;
+            }
+            if write_direct(num, bits) {
+                return Ok(());
+            }
+            // csrrw x0, csr, a0
+            let ok = probe_csr_instruction!(
+                csr_table!("0x51073"),
+                offset = in(reg) usize::from(num) * 8,
+                tmp = out(reg) _,
+                in("a0") bits,
+            );
+            if ok {
+                Ok(())
+            } else {
+                Err(CsrError::Unimplemented)
+            }
+        }
+
+        #[cfg(not(riscv))]
+        () => {
+            let _ = bits;
+            unimplemented!()
+        }
+    }
+}
+
+/// Reads the CSRs modified by the probe, returning `None` for the other CSRs
+#[cfg(riscv)]
+unsafe fn read_direct(num: u16) -> Option<usize> {
+    let r: usize;
+    match num {
+        #[cfg(feature = "s-mode")]
+        0x100 => core::arch::asm!("csrr {0}, sstatus", out(reg) r),
+        #[cfg(feature = "s-mode")]
+        0x105 => core::arch::asm!("csrr {0}, stvec", out(reg) r),
+        #[cfg(not(feature = "s-mode"))]
+        0x300 => core::arch::asm!("csrr {0}, mstatus", out(reg) r),
+        #[cfg(not(feature = "s-mode"))]
+        0x305 => core::arch::asm!("csrr {0}, mtvec", out(reg) r),
+        _ => return None,
+    }
+    Some(r)
+}
+
+/// Writes the CSRs modified by the probe, returning `false` for the other CSRs
+#[cfg(riscv)]
+unsafe fn write_direct(num: u16, bits: usize) -> bool {
+    match num {
+        0x100 => core::arch::asm!("csrw sstatus, {0}", in(reg) bits),
+        #[cfg(feature = "s-mode")]
+        0x105 => core::arch::asm!("csrw stvec, {0}", in(reg) bits),
+        #[cfg(not(feature = "s-mode"))]
+        0x300 => core::arch::asm!("csrw mstatus, {0}", in(reg) bits),
+        #[cfg(not(feature = "s-mode"))]
+        0x305 => core::arch::asm!("csrw mtvec, {0}", in(reg) bits),
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_dynamic, write_dynamic, CsrError};
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(read_dynamic(0x1000), Err(CsrError::OutOfRange));
+        assert_eq!(
+            unsafe { write_dynamic(u16::MAX, 0) },
+            Err(CsrError::OutOfRange)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Floating-point control and status register
 
 use bit_field::BitField;
+use core::ops::{BitAnd, BitOr, BitOrAssign};
 
 /// Floating-point control and status register
 #[derive(Clone, Copy, Debug)]
@@ -9,11 +10,11 @@ pub struct FCSR {
 }
 
 /// Accrued Exception Flags
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Flags(u32);
 
 /// Accrued Exception Flag
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Flag {
     /// Inexact
     NX = 0b00001,
@@ -32,6 +33,44 @@ pub enum Flag {
 }
 
 impl Flags {
+    /// No flag set
+    #[inline]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// All the flags set
+    #[inline]
+    pub const fn all() -> Self {
+        Self(0b11111)
+    }
+
+    /// Constructs the flags from the raw bits of `fflags`; bits other than the five flags are
+    /// ignored
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits & 0b11111)
+    }
+
+    /// Returns the flags as raw bits
+    #[inline]
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Whether no flag is set
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether all the flags of `other` are set
+    #[inline]
+    pub fn contains<F: Into<Flags>>(&self, other: F) -> bool {
+        let other = other.into();
+        self.0 & other.0 == other.0
+    }
+
     /// Inexact
     #[inline]
     pub fn nx(&self) -> bool {
@@ -63,6 +102,47 @@ impl Flags {
     }
 }
 
+impl From<Flag> for Flags {
+    #[inline]
+    fn from(flag: Flag) -> Self {
+        Self(flag as u32)
+    }
+}
+
+impl<F: Into<Flags>> BitOr<F> for Flags {
+    type Output = Flags;
+
+    #[inline]
+    fn bitor(self, rhs: F) -> Flags {
+        Flags(self.0 | rhs.into().0)
+    }
+}
+
+impl<F: Into<Flags>> BitOr<F> for Flag {
+    type Output = Flags;
+
+    #[inline]
+    fn bitor(self, rhs: F) -> Flags {
+        Flags(self as u32 | rhs.into().0)
+    }
+}
+
+impl<F: Into<Flags>> BitOrAssign<F> for Flags {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: F) {
+        self.0 |= rhs.into().0;
+    }
+}
+
+impl<F: Into<Flags>> BitAnd<F> for Flags {
+    type Output = Flags;
+
+    #[inline]
+    fn bitand(self, rhs: F) -> Flags {
+        Flags(self.0 & rhs.into().0)
+    }
+}
+
 /// Rounding Mode
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RoundingMode {
@@ -74,6 +154,21 @@ pub enum RoundingMode {
     Invalid = 0b111,
 }
 
+impl RoundingMode {
+    /// Decodes a 3-bit rounding mode
+    #[inline]
+    pub(crate) fn from_bits(bits: usize) -> Self {
+        match bits & 0b111 {
+            0b000 => RoundingMode::RoundToNearestEven,
+            0b001 => RoundingMode::RoundTowardsZero,
+            0b010 => RoundingMode::RoundDown,
+            0b011 => RoundingMode::RoundUp,
+            0b100 => RoundingMode::RoundToNearestMaxMagnitude,
+            _ => RoundingMode::Invalid,
+        }
+    }
+}
+
 impl FCSR {
     /// Returns the contents of the register as raw bits
     #[inline]
@@ -90,14 +185,7 @@ impl FCSR {
     /// Rounding Mode
     #[inline]
     pub fn frm(&self) -> RoundingMode {
-        match self.bits.get_bits(5..8) {
-            0b000 => RoundingMode::RoundToNearestEven,
-            0b001 => RoundingMode::RoundTowardsZero,
-            0b010 => RoundingMode::RoundDown,
-            0b011 => RoundingMode::RoundUp,
-            0b100 => RoundingMode::RoundToNearestMaxMagnitude,
-            _ => RoundingMode::Invalid,
-        }
+        RoundingMode::from_bits(self.bits.get_bits(5..8) as usize)
     }
 }
 
@@ -113,12 +201,13 @@ pub fn read() -> FCSR {
     }
 }
 
-/// Writes the CSR
+/// Sets the `frm` field
+///
+/// Writes the `frm` CSR, so that accrued exception flags raised concurrently (e.g. by an interrupt
+/// handler) are not lost.
 #[inline]
 pub unsafe fn set_rounding_mode(frm: RoundingMode) {
-    let old = read();
-    let bits = ((frm as u32) << 5) | old.fflags().0;
-    _write(bits as usize);
+    super::frm::write(frm);
 }
 
 /// Resets `fflags` field bits
@@ -133,3 +222,24 @@ pub unsafe fn clear_flags() {
 pub unsafe fn clear_flag(flag: Flag) {
     _clear(flag as usize);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Flag, Flags};
+
+    #[test]
+    fn flags() {
+        let flags = Flag::OF | Flag::NX;
+        assert_eq!(flags.bits(), 0b00101);
+        assert!(flags.contains(Flag::OF));
+        assert!(flags.contains(Flag::NX | Flag::OF));
+        assert!(!flags.contains(Flag::OF | Flag::DZ));
+        assert_eq!(flags & Flag::NX, Flags::from(Flag::NX));
+        assert!((flags & Flag::UF).is_empty());
+        assert_eq!(Flags::from_bits(0xFF), Flags::all());
+
+        let mut flags = Flags::empty();
+        flags |= Flag::NV;
+        assert!(flags.nv() && !flags.dz());
+    }
+}
@@ -0,0 +1,44 @@
+//! fflags register
+//!
+//! The accrued exception flags field of `fcsr`, accessible on its own.
+
+pub use super::fcsr::{Flag, Flags};
+
+read_csr!(0x001);
+write_csr!(0x001);
+read_clear!(0x001);
+
+/// Reads the CSR
+#[inline]
+pub fn read() -> Flags {
+    Flags::from_bits(unsafe { _read() } as u32)
+}
+
+/// Writes the CSR
+///
+/// # Safety
+///
+/// Discards the flags raised so far, which other code may be waiting for.
+#[inline]
+pub unsafe fn write(flags: Flags) {
+    _write(flags.bits() as usize);
+}
+
+/// Resets the bits of `flags`, and returns which of them were set, atomically
+///
+/// ```no_run
+/// use riscv::register::fflags::{self, Flag};
+///
+/// let raised = unsafe { fflags::test_and_clear(Flag::OF | Flag::UF) };
+/// if raised.contains(Flag::OF) {
+///     // handle the overflow
+/// }
+/// ```
+///
+/// # Safety
+///
+/// See `write`.
+#[inline]
+pub unsafe fn test_and_clear(flags: Flags) -> Flags {
+    Flags::from_bits(_read_clear(flags.bits() as usize) as u32) & flags
+}
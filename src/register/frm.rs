@@ -0,0 +1,43 @@
+//! frm register
+//!
+//! The rounding mode field of `fcsr`, accessible on its own.
+
+pub use super::fcsr::RoundingMode;
+
+read_csr!(0x002);
+write_csr!(0x002);
+
+/// Reads the CSR
+#[inline]
+pub fn read() -> RoundingMode {
+    RoundingMode::from_bits(unsafe { _read() })
+}
+
+/// Writes the CSR
+///
+/// Only the rounding mode is changed, the accrued exception flags of `fcsr` are left untouched.
+///
+/// # Safety
+///
+/// The compiler assumes the default rounding mode (`RoundToNearestEven`) when evaluating
+/// floating-point operations at compile time.
+#[inline]
+pub unsafe fn write(frm: RoundingMode) {
+    _write(frm as usize);
+}
+
+/// Runs `f` with the rounding mode set to `frm`, then restores the previous rounding mode
+///
+/// # Safety
+///
+/// See `write`.
+#[inline]
+pub unsafe fn with_rounding_mode<F, R>(frm: RoundingMode, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let old = _swap(frm as usize);
+    let r = f();
+    _write(old);
+    r
+}
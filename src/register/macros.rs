@@ -1,46 +1,308 @@
-macro_rules! read_csr {
-    ($csr_number:literal) => {
+/// Defines the contents of a CSR module from a table of its fields
+///
+/// The first line gives the name of the register type, the CSR number and the accesses to
+/// generate:
+///
+/// - `read`: `fn read() -> Register`
+/// - `write`: `unsafe fn write(bits: usize)` and `unsafe fn swap(bits: usize) -> Register`, the
+///   former also used by the field setters
+/// - `set`, `clear`: `unsafe fn read_set(mask: usize) -> Register` and `unsafe fn
+///   read_clear(mask: usize) -> Register`, and the private `_set(mask)` and `_clear(mask)` used
+///   by the `set_`/`clear_` helpers of the single-bit fields
+///
+/// It is followed by the fields, each with a getter on the register type and optional
+/// modifiers:
+///
+/// - `name: bit` returns a `bool`; `name: bit, set_name, clear_name` also generates
+///   `unsafe fn set_name()` and `unsafe fn clear_name()` (with `set` and `clear`)
+/// - `name: lo..hi` returns the bits `lo` to `hi` (excluded) as a `usize`; `name: lo..hi,
+///   set_name` also generates `unsafe fn set_name(value: usize)` (with `read` and `write`),
+///   which truncates `value` to the width of the field
+/// - `name: lo..hi as Enum` and `name: lo..hi as Enum, set_name` are the same with an enum
+///   declared in the table (or in the table of another register of the crate), which must have a
+///   variant for each value of the field: this is checked at compile time
+/// - `name: lo..hi as Option<Enum>` and `name: lo..hi as Option<Enum>, set_name` are the same
+///   for an enum without a variant for the reserved values of the field, which the getter returns
+///   as `None`
+///
+/// The built-in registers are defined with the same macros.
+///
+/// # Example
+///
+/// ```no_run
+/// mod mcustom {
+///     riscv::csr! {
+///         /// mcustom register
+///         Mcustom: 0x7C0, read, write, set, clear;
+///
+///         /// Enable
+///         enable: 0, set_enable, clear_enable;
+///         /// Operating mode
+///         mode: 2..4 as Mode, set_mode;
+///         /// Threshold
+///         threshold: 8..16, set_threshold;
+///         /// Privilege level
+///         level: 16..18 as Option<Level>;
+///
+///         /// Operating mode
+///         pub enum Mode {
+///             Off = 0,
+///             Fast = 1,
+///             Slow = 2,
+///             Auto = 3,
+///         }
+///
+///         /// Privilege level, `2` being reserved
+///         pub enum Level {
+///             User = 0,
+///             Supervisor = 1,
+///             Machine = 3,
+///         }
+///     }
+/// }
+///
+/// let mcustom = mcustom::read();
+/// if mcustom.enable() && mcustom.mode() == mcustom::Mode::Off {
+///     unsafe { mcustom::set_mode(mcustom::Mode::Auto) };
+/// }
+/// ```
+#[macro_export]
+macro_rules! csr {
+    (@_read $csr_number:literal) => {
         /// Reads the CSR
         #[inline]
         unsafe fn _read() -> usize {
             match () {
-                #[cfg(riscv)]
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
                 () => {
                     let r: usize;
                     core::arch::asm!(concat!("csrrs {0}, ", stringify!($csr_number), ", x0"), out(reg) r);
                     r
                 }
 
-                #[cfg(not(riscv))]
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
                 () => unimplemented!(),
             }
         }
     };
-}
-
-macro_rules! read_csr_rv32 {
-    ($csr_number:literal) => {
+    (@_read_rv32 $csr_number:literal) => {
         /// Reads the CSR
         #[inline]
         unsafe fn _read() -> usize {
             match () {
-                #[cfg(riscv32)]
+                #[cfg(target_arch = "riscv32")]
                 () => {
                     let r: usize;
                     core::arch::asm!(concat!("csrrs {0}, ", stringify!($csr_number), ", x0"), out(reg) r);
                     r
                 }
 
-                #[cfg(not(riscv32))]
+                #[cfg(not(target_arch = "riscv32"))]
                 () => unimplemented!(),
             }
         }
     };
-}
+    (@_write $csr_number:literal) => {
+        /// Writes the CSR
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _write(bits: usize) {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => core::arch::asm!(concat!("csrrw x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
 
-macro_rules! read_csr_as {
-    ($register:ident, $csr_number:literal) => {
-        read_csr!($csr_number);
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+
+        $crate::csr!(@_swap $csr_number);
+
+        /// Writes the CSR and returns its previous value, atomically
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn swap(bits: usize) -> _Register {
+            _register(_swap(bits))
+        }
+    };
+    (@_write_rv32 $csr_number:literal) => {
+        /// Writes the CSR
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _write(bits: usize) {
+            match () {
+                #[cfg(target_arch = "riscv32")]
+                () => core::arch::asm!(concat!("csrrw x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
+
+                #[cfg(not(target_arch = "riscv32"))]
+                () => unimplemented!(),
+            }
+        }
+
+        $crate::csr!(@_swap_rv32 $csr_number);
+
+        /// Writes the CSR and returns its previous value, atomically
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn swap(bits: usize) -> _Register {
+            _register(_swap(bits))
+        }
+    };
+    (@_set $csr_number:literal) => {
+        /// Set the CSR
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _set(bits: usize) {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => core::arch::asm!(concat!("csrrs x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
+
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+
+        $crate::csr!(@_read_set $csr_number);
+
+        /// Sets the bits of `mask` and returns the previous value of the CSR, atomically
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn read_set(mask: usize) -> _Register {
+            _register(_read_set(mask))
+        }
+    };
+    (@_clear $csr_number:literal) => {
+        /// Clear the CSR
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _clear(bits: usize) {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => core::arch::asm!(concat!("csrrc x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
+
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+
+        $crate::csr!(@_read_clear $csr_number);
+
+        /// Clears the bits of `mask` and returns the previous value of the CSR, atomically
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn read_clear(mask: usize) -> _Register {
+            _register(_read_clear(mask))
+        }
+    };
+    (@_swap $csr_number:literal) => {
+        /// Writes the CSR and returns its previous value
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _swap(bits: usize) -> usize {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrw {0}, ", stringify!($csr_number), ", {1}"), out(reg) r, in(reg) bits);
+                    r
+                }
+
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+    };
+    (@_swap_rv32 $csr_number:literal) => {
+        /// Writes the CSR and returns its previous value
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _swap(bits: usize) -> usize {
+            match () {
+                #[cfg(target_arch = "riscv32")]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrw {0}, ", stringify!($csr_number), ", {1}"), out(reg) r, in(reg) bits);
+                    r
+                }
+
+                #[cfg(not(target_arch = "riscv32"))]
+                () => unimplemented!(),
+            }
+        }
+    };
+    (@_read_set $csr_number:literal) => {
+        /// Sets bits of the CSR and returns its previous value
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _read_set(bits: usize) -> usize {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrs {0}, ", stringify!($csr_number), ", {1}"), out(reg) r, in(reg) bits);
+                    r
+                }
+
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+    };
+    (@_read_clear $csr_number:literal) => {
+        /// Clears bits of the CSR and returns its previous value
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _read_clear(bits: usize) -> usize {
+            match () {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrc {0}, ", stringify!($csr_number), ", {1}"), out(reg) r, in(reg) bits);
+                    r
+                }
+
+                #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+                () => unimplemented!(),
+            }
+        }
+    };
+    (@_register usize) => {
+        #[allow(dead_code)]
+        type _Register = usize;
+
+        /// Converts the value of the CSR
+        #[inline]
+        #[allow(dead_code)]
+        fn _register(bits: usize) -> _Register {
+            bits
+        }
+    };
+    (@_register $register:ident) => {
+        #[allow(dead_code)]
+        type _Register = $register;
+
+        /// Converts the value of the CSR
+        #[inline]
+        #[allow(dead_code)]
+        fn _register(bits: usize) -> _Register {
+            $register { bits }
+        }
+    };
+
+    (@access $register:ident $csr_number:literal read) => {
+        $crate::csr!(@_read $csr_number);
 
         /// Reads the CSR
         #[inline]
@@ -50,6 +312,246 @@ macro_rules! read_csr_as {
             }
         }
     };
+    (@access $register:ident $csr_number:literal write) => {
+        $crate::csr!(@_write $csr_number);
+
+        /// Writes the CSR
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn write(bits: usize) {
+            _write(bits)
+        }
+    };
+    (@access $register:ident $csr_number:literal set) => {
+        $crate::csr!(@_set $csr_number);
+    };
+    (@access $register:ident $csr_number:literal clear) => {
+        $crate::csr!(@_clear $csr_number);
+    };
+    (@access $register:ident $csr_number:literal $access:ident) => {
+        compile_error!(concat!("unknown CSR access `", stringify!($access), "`"));
+    };
+
+    (@fields $register:ident;) => {};
+    (@fields $register:ident;
+        $(#[$attr:meta])*
+        pub enum $enum:ident {
+            $($(#[$variant_attr:meta])* $variant:ident = $value:literal),+ $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+        pub enum $enum {
+            $($(#[$variant_attr])* $variant = $value),+
+        }
+
+        // Carries the `cfg` attributes of the enum
+        $(#[$attr])*
+        const _: () = {
+            impl $enum {
+                /// Values of the variants
+                #[allow(dead_code)]
+                pub(crate) const VALUES: &'static [usize] = &[$($value),+];
+
+                /// Converts the value of a field, returning `None` if no variant has this value
+                #[inline]
+                #[allow(dead_code)]
+                pub(crate) fn from_bits(bits: usize) -> Option<Self> {
+                    match bits {
+                        $($value => Some(Self::$variant),)+
+                        _ => None,
+                    }
+                }
+            }
+        };
+
+        $crate::csr!(@fields $register; $($rest)*);
+    };
+    (@fields $register:ident;
+        $(#[$attr:meta])*
+        $field:ident: $lo:literal..$hi:literal as Option<$enum:ident> $(, $setter:ident)?;
+        $($rest:tt)*
+    ) => {
+        impl $register {
+            $(#[$attr])*
+            #[inline]
+            pub fn $field(&self) -> Option<$enum> {
+                const MASK: usize = usize::MAX >> (usize::BITS - ($hi - $lo));
+                $crate::csr!(@check_enum $enum MASK);
+                $enum::from_bits((self.bits >> $lo) & MASK)
+            }
+        }
+
+        $crate::csr!(@setter [$(#[$attr])*] $lo $hi [$enum] [$($setter)?]);
+        $crate::csr!(@fields $register; $($rest)*);
+    };
+    (@fields $register:ident;
+        $(#[$attr:meta])*
+        $field:ident: $lo:literal..$hi:literal as $enum:ident $(, $setter:ident)?;
+        $($rest:tt)*
+    ) => {
+        impl $register {
+            $(#[$attr])*
+            #[inline]
+            pub fn $field(&self) -> $enum {
+                const MASK: usize = usize::MAX >> (usize::BITS - ($hi - $lo));
+                $crate::csr!(@check_enum $enum MASK);
+                const _: () = assert!(
+                    $enum::VALUES.len() == MASK + 1,
+                    "the enum must have a variant for each value of the field, or be used as `Option<Enum>`",
+                );
+                match $enum::from_bits((self.bits >> $lo) & MASK) {
+                    Some(value) => value,
+                    // Excluded by the assertions above
+                    None => unreachable!(),
+                }
+            }
+        }
+
+        $crate::csr!(@setter [$(#[$attr])*] $lo $hi [$enum] [$($setter)?]);
+        $crate::csr!(@fields $register; $($rest)*);
+    };
+    (@fields $register:ident;
+        $(#[$attr:meta])*
+        $field:ident: $lo:literal..$hi:literal $(, $setter:ident)?;
+        $($rest:tt)*
+    ) => {
+        impl $register {
+            $(#[$attr])*
+            #[inline]
+            pub fn $field(&self) -> usize {
+                const MASK: usize = usize::MAX >> (usize::BITS - ($hi - $lo));
+                (self.bits >> $lo) & MASK
+            }
+        }
+
+        $crate::csr!(@setter [$(#[$attr])*] $lo $hi [] [$($setter)?]);
+        $crate::csr!(@fields $register; $($rest)*);
+    };
+    (@fields $register:ident;
+        $(#[$attr:meta])*
+        $field:ident: $bit:literal $(, $set:ident, $clear:ident)?;
+        $($rest:tt)*
+    ) => {
+        impl $register {
+            $(#[$attr])*
+            #[inline]
+            pub fn $field(&self) -> bool {
+                self.bits & (1 << $bit) != 0
+            }
+        }
+
+        $crate::csr!(@set_clear [$(#[$attr])*] $bit [$($set $clear)?]);
+        $crate::csr!(@fields $register; $($rest)*);
+    };
+
+    (@check_enum $enum:ident $mask:ident) => {
+        const _: () = {
+            let mut i = 0;
+            while i < $enum::VALUES.len() {
+                assert!($enum::VALUES[i] <= $mask, "enum value larger than the field");
+                i += 1;
+            }
+        };
+    };
+
+    (@set_clear [$($attr:tt)*] $bit:literal []) => {};
+    (@set_clear [$($attr:tt)*] $bit:literal [$set:ident $clear:ident]) => {
+        $($attr)*
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $set() {
+            _set(1 << $bit);
+        }
+
+        $($attr)*
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $clear() {
+            _clear(1 << $bit);
+        }
+    };
+
+    (@setter [$($attr:tt)*] $lo:literal $hi:literal [$($enum:ident)?] []) => {};
+    (@setter [$($attr:tt)*] $lo:literal $hi:literal [] [$setter:ident]) => {
+        $($attr)*
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $setter(value: usize) {
+            const MASK: usize = usize::MAX >> (usize::BITS - ($hi - $lo));
+            _write((_read() & !(MASK << $lo)) | ((value & MASK) << $lo));
+        }
+    };
+    (@setter [$($attr:tt)*] $lo:literal $hi:literal [$enum:ident] [$setter:ident]) => {
+        $($attr)*
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $setter(value: $enum) {
+            const MASK: usize = usize::MAX >> (usize::BITS - ($hi - $lo));
+            _write((_read() & !(MASK << $lo)) | ((value as usize & MASK) << $lo));
+        }
+    };
+
+    (
+        $(#[$attr:meta])*
+        $register:ident: $csr_number:literal $(, $access:ident)*;
+        $($fields:tt)*
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug)]
+        pub struct $register {
+            bits: usize,
+        }
+
+        impl $register {
+            /// Returns the contents of the register as raw bits
+            #[inline]
+            pub fn bits(&self) -> usize {
+                self.bits
+            }
+        }
+
+        $crate::csr!(@_register $register);
+        $($crate::csr!(@access $register $csr_number $access);)*
+        $crate::csr!(@fields $register; $($fields)*);
+    };
+}
+
+macro_rules! read_csr {
+    ($csr_number:literal) => {
+        $crate::csr!(@_read $csr_number);
+        $crate::csr!(@_register usize);
+    };
+}
+
+macro_rules! read_csr_rv32 {
+    ($csr_number:literal) => {
+        $crate::csr!(@_read_rv32 $csr_number);
+        $crate::csr!(@_register usize);
+    };
+}
+
+macro_rules! read_csr_as {
+    ($register:ident, $csr_number:literal) => {
+        $crate::csr!(@_register $register);
+        $crate::csr!(@access $register $csr_number read);
+    };
 }
 
 macro_rules! read_csr_as_usize {
@@ -78,35 +580,13 @@ macro_rules! read_csr_as_usize_rv32 {
 
 macro_rules! write_csr {
     ($csr_number:literal) => {
-        /// Writes the CSR
-        #[inline]
-        #[allow(unused_variables)]
-        unsafe fn _write(bits: usize) {
-            match () {
-                #[cfg(riscv)]
-                () => core::arch::asm!(concat!("csrrw x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
-
-                #[cfg(not(riscv))]
-                () => unimplemented!(),
-            }
-        }
+        $crate::csr!(@_write $csr_number);
     };
 }
 
 macro_rules! write_csr_rv32 {
     ($csr_number:literal) => {
-        /// Writes the CSR
-        #[inline]
-        #[allow(unused_variables)]
-        unsafe fn _write(bits: usize) {
-            match () {
-                #[cfg(riscv32)]
-                () => core::arch::asm!(concat!("csrrw x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
-
-                #[cfg(not(riscv32))]
-                () => unimplemented!(),
-            }
-        }
+        $crate::csr!(@_write_rv32 $csr_number);
     };
 }
 
@@ -161,13 +641,190 @@ macro_rules! claim_csr_as {
 
 macro_rules! set {
     ($csr_number:literal) => {
-        /// Set the CSR
+        $crate::csr!(@_set $csr_number);
+    };
+}
+
+macro_rules! clear {
+    ($csr_number:literal) => {
+        $crate::csr!(@_clear $csr_number);
+    };
+}
+
+macro_rules! swap {
+    ($csr_number:literal) => {
+        $crate::csr!(@_swap $csr_number);
+    };
+}
+
+macro_rules! read_clear {
+    ($csr_number:literal) => {
+        $crate::csr!(@_read_clear $csr_number);
+    };
+}
+
+macro_rules! read_set_clear_csr_imm {
+    ($register:ident, $csr_number:literal, $(#[$attr:meta])*, $set_field:ident, $clear_field:ident, $e:expr) => {
+        $(#[$attr])*
+        ///
+        /// Sets the bit with `csrrsi` and returns the previous value of the CSR.
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $set_field() -> $register {
+            const _: () = assert!($e < 32, "immediate masks are 5 bits wide");
+            match () {
+                #[cfg(riscv)]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrsi {0}, ", stringify!($csr_number), ", ", stringify!($e)), out(reg) r);
+                    $register { bits: r }
+                }
+
+                #[cfg(not(riscv))]
+                () => unimplemented!(),
+            }
+        }
+
+        $(#[$attr])*
+        ///
+        /// Clears the bit with `csrrci` and returns the previous value of the CSR.
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn $clear_field() -> $register {
+            const _: () = assert!($e < 32, "immediate masks are 5 bits wide");
+            match () {
+                #[cfg(riscv)]
+                () => {
+                    let r: usize;
+                    core::arch::asm!(concat!("csrrci {0}, ", stringify!($csr_number), ", ", stringify!($e)), out(reg) r);
+                    $register { bits: r }
+                }
+
+                #[cfg(not(riscv))]
+                () => unimplemented!(),
+            }
+        }
+    };
+}
+
+/// Name of the trap CSR `$csr` of the privilege mode the crate runs in, and the interrupt enable
+/// bit (`ie`) of its status CSR
+#[cfg(all(riscv, not(feature = "s-mode")))]
+macro_rules! trap_csr {
+    (status) => {
+        "mstatus"
+    };
+    (ie) => {
+        "8"
+    };
+    (tvec) => {
+        "mtvec"
+    };
+    (epc) => {
+        "mepc"
+    };
+    (cause) => {
+        "mcause"
+    };
+    (tval) => {
+        "mtval"
+    };
+}
+
+/// Name of the trap CSR `$csr` of the privilege mode the crate runs in, and the interrupt enable
+/// bit (`ie`) of its status CSR
+#[cfg(all(riscv, feature = "s-mode"))]
+macro_rules! trap_csr {
+    (status) => {
+        "sstatus"
+    };
+    (ie) => {
+        "2"
+    };
+    (tvec) => {
+        "stvec"
+    };
+    (epc) => {
+        "sepc"
+    };
+    (cause) => {
+        "scause"
+    };
+    (tval) => {
+        "stval"
+    };
+}
+
+/// Executes the CSR instruction `$insn` with a temporary trap vector pointing past it, returning
+/// whether it completed without raising an exception
+#[cfg(riscv)]
+macro_rules! probe_csr_instruction {
+    ($insn:expr, $($operands:tt)*) => {
+        $crate::register::probe::catch(|| {
+            let ok: usize;
+            core::arch::asm!(
+                "li {ok}, 0",
+                "lla {tvec}, 1f",
+                concat!("csrrw {tvec}, ", trap_csr!(tvec), ", {tvec}"),
+                $insn,
+                "li {ok}, 1",
+                ".p2align 2",
+                "1:",
+                concat!("csrw ", trap_csr!(tvec), ", {tvec}"),
+                ok = out(reg) ok,
+                tvec = out(reg) _,
+                $($operands)*
+            );
+            ok != 0
+        })
+    };
+}
+
+macro_rules! try_read {
+    ($csr_number:literal) => {
+        /// Reads the CSR, returning `None` if the access raises an exception
+        #[inline]
+        unsafe fn _try_read() -> Option<usize> {
+            match () {
+                #[cfg(riscv)]
+                () => {
+                    let mut r: usize = 0;
+                    let ok = probe_csr_instruction!(
+                        concat!("csrrs {r}, ", stringify!($csr_number), ", x0"),
+                        r = out(reg) r,
+                    );
+                    if ok {
+                        Some(r)
+                    } else {
+                        None
+                    }
+                }
+
+                #[cfg(not(riscv))]
+                () => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! try_write {
+    ($csr_number:literal) => {
+        /// Writes the CSR, returning `false` if the access raises an exception
         #[inline]
         #[allow(unused_variables)]
-        unsafe fn _set(bits: usize) {
+        unsafe fn _try_write(bits: usize) -> bool {
             match () {
                 #[cfg(riscv)]
-                () => core::arch::asm!(concat!("csrrs x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
+                () => probe_csr_instruction!(
+                    concat!("csrrw x0, ", stringify!($csr_number), ", {bits}"),
+                    bits = in(reg) bits,
+                ),
 
                 #[cfg(not(riscv))]
                 () => unimplemented!(),
@@ -176,15 +833,65 @@ macro_rules! set {
     };
 }
 
-macro_rules! clear {
+macro_rules! try_read_rv32 {
     ($csr_number:literal) => {
-        /// Clear the CSR
+        /// Reads the CSR, returning `None` if the access raises an exception
+        #[inline]
+        unsafe fn _try_read() -> Option<usize> {
+            match () {
+                #[cfg(riscv32)]
+                () => {
+                    let mut r: usize = 0;
+                    let ok = probe_csr_instruction!(
+                        concat!("csrrs {r}, ", stringify!($csr_number), ", x0"),
+                        r = out(reg) r,
+                    );
+                    if ok {
+                        Some(r)
+                    } else {
+                        None
+                    }
+                }
+
+                #[cfg(not(riscv32))]
+                () => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! try_write_rv32 {
+    ($csr_number:literal) => {
+        /// Writes the CSR, returning `false` if the access raises an exception
         #[inline]
         #[allow(unused_variables)]
-        unsafe fn _clear(bits: usize) {
+        unsafe fn _try_write(bits: usize) -> bool {
+            match () {
+                #[cfg(riscv32)]
+                () => probe_csr_instruction!(
+                    concat!("csrrw x0, ", stringify!($csr_number), ", {bits}"),
+                    bits = in(reg) bits,
+                ),
+
+                #[cfg(not(riscv32))]
+                () => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! try_set {
+    ($csr_number:literal) => {
+        /// Sets bits of the CSR, returning `false` if the access raises an exception
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _try_set(bits: usize) -> bool {
             match () {
                 #[cfg(riscv)]
-                () => core::arch::asm!(concat!("csrrc x0, ", stringify!($csr_number), ", {0}"), in(reg) bits),
+                () => probe_csr_instruction!(
+                    concat!("csrrs x0, ", stringify!($csr_number), ", {bits}"),
+                    bits = in(reg) bits,
+                ),
 
                 #[cfg(not(riscv))]
                 () => unimplemented!(),
@@ -193,6 +900,173 @@ macro_rules! clear {
     };
 }
 
+macro_rules! try_clear {
+    ($csr_number:literal) => {
+        /// Clears bits of the CSR, returning `false` if the access raises an exception
+        #[inline]
+        #[allow(unused_variables)]
+        unsafe fn _try_clear(bits: usize) -> bool {
+            match () {
+                #[cfg(riscv)]
+                () => probe_csr_instruction!(
+                    concat!("csrrc x0, ", stringify!($csr_number), ", {bits}"),
+                    bits = in(reg) bits,
+                ),
+
+                #[cfg(not(riscv))]
+                () => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! try_read_csr_as {
+    ($register:ident, $csr_number:literal) => {
+        try_read!($csr_number);
+
+        /// Reads the CSR, returning `None` if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        #[inline]
+        pub fn try_read() -> Option<$register> {
+            unsafe { _try_read() }.map(|bits| $register { bits })
+        }
+    };
+}
+
+macro_rules! try_read_csr_as_usize {
+    ($csr_number:literal) => {
+        try_read!($csr_number);
+
+        /// Reads the CSR, returning `None` if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        #[inline]
+        pub fn try_read() -> Option<usize> {
+            unsafe { _try_read() }
+        }
+    };
+}
+
+macro_rules! try_write_csr_as_usize {
+    ($csr_number:literal) => {
+        try_write!($csr_number);
+
+        /// Writes the CSR, returning an error if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        #[inline]
+        pub fn try_write(bits: usize) -> Result<(), $crate::register::probe::Unimplemented> {
+            if unsafe { _try_write(bits) } {
+                Ok(())
+            } else {
+                Err($crate::register::probe::Unimplemented)
+            }
+        }
+    };
+}
+
+macro_rules! try_read_csr_as_usize_rv32 {
+    ($csr_number:literal) => {
+        try_read_rv32!($csr_number);
+
+        /// Reads the CSR, returning `None` if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        #[inline]
+        pub fn try_read() -> Option<usize> {
+            unsafe { _try_read() }
+        }
+    };
+}
+
+macro_rules! try_write_csr_as_usize_rv32 {
+    ($csr_number:literal) => {
+        try_write_rv32!($csr_number);
+
+        /// Writes the CSR, returning an error if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        #[inline]
+        pub fn try_write(bits: usize) -> Result<(), $crate::register::probe::Unimplemented> {
+            if unsafe { _try_write(bits) } {
+                Ok(())
+            } else {
+                Err($crate::register::probe::Unimplemented)
+            }
+        }
+    };
+}
+
+macro_rules! try_write_csr {
+    ($csr_number:literal) => {
+        try_write!($csr_number);
+
+        /// Writes the CSR, returning an error if it is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn try_write(bits: usize) -> Result<(), $crate::register::probe::Unimplemented> {
+            if _try_write(bits) {
+                Ok(())
+            } else {
+                Err($crate::register::probe::Unimplemented)
+            }
+        }
+    };
+}
+
+macro_rules! try_set_clear_csr {
+    ($csr_number:literal) => {
+        try_set!($csr_number);
+        try_clear!($csr_number);
+
+        /// Sets the bits of `mask`, returning an error if the CSR is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn try_set(mask: usize) -> Result<(), $crate::register::probe::Unimplemented> {
+            if _try_set(mask) {
+                Ok(())
+            } else {
+                Err($crate::register::probe::Unimplemented)
+            }
+        }
+
+        /// Clears the bits of `mask`, returning an error if the CSR is not implemented
+        ///
+        /// The illegal instruction exception raised by an unimplemented CSR is caught, see
+        /// [`probe`](crate::register::probe).
+        ///
+        /// # Safety
+        ///
+        /// Changing the CSR may break the assumptions of the running code.
+        #[inline]
+        pub unsafe fn try_clear(mask: usize) -> Result<(), $crate::register::probe::Unimplemented> {
+            if _try_clear(mask) {
+                Ok(())
+            } else {
+                Err($crate::register::probe::Unimplemented)
+            }
+        }
+    };
+}
+
 macro_rules! set_csr {
     ($(#[$attr:meta])*, $set_field:ident, $e:expr) => {
         $(#[$attr])*
@@ -242,6 +1116,73 @@ macro_rules! read_composite_csr {
     };
 }
 
+macro_rules! write_composite_csr {
+    ($hi:path, $lo:path) => {
+        /// Writes the CSR as a 64-bit value
+        ///
+        /// On RV32 the low half is first set to all ones, so that no intermediate value of the
+        /// register pair is lower than both the old and the new value.
+        #[inline]
+        pub fn write64(bits: u64) {
+            match () {
+                #[cfg(riscv32)]
+                () => {
+                    $lo(usize::MAX);
+                    $hi((bits >> 32) as usize);
+                    $lo(bits as usize);
+                }
+
+                #[cfg(not(riscv32))]
+                () => $lo(bits as usize),
+            }
+        }
+    };
+}
+
+macro_rules! try_read_composite_csr {
+    ($hi:expr, $lo:expr) => {
+        /// Reads the CSR as a 64-bit value, returning `None` if it is not implemented
+        #[inline]
+        pub fn try_read64() -> Option<u64> {
+            match () {
+                #[cfg(riscv32)]
+                () => loop {
+                    let hi = $hi?;
+                    let lo = $lo?;
+                    if Some(hi) == $hi {
+                        return Some(((hi as u64) << 32) | lo as u64);
+                    }
+                },
+
+                #[cfg(not(riscv32))]
+                () => $lo.map(|lo| lo as u64),
+            }
+        }
+    };
+}
+
+macro_rules! try_write_composite_csr {
+    ($hi:path, $lo:path) => {
+        /// Writes the CSR as a 64-bit value, returning an error if it is not implemented
+        ///
+        /// See `write64`.
+        #[inline]
+        pub fn try_write64(bits: u64) -> Result<(), $crate::register::probe::Unimplemented> {
+            match () {
+                #[cfg(riscv32)]
+                () => {
+                    $lo(usize::MAX)?;
+                    $hi((bits >> 32) as usize)?;
+                    $lo(bits as usize)
+                }
+
+                #[cfg(not(riscv32))]
+                () => $lo(bits as usize),
+            }
+        }
+    };
+}
+
 macro_rules! set_pmp {
     () => {
         /// Set the pmp configuration corresponding to the index
@@ -278,3 +1219,81 @@ macro_rules! clear_pmp {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    #[allow(dead_code)]
+    mod raw {
+        read_csr!(0x7C0);
+        write_csr!(0x7C0);
+        set!(0x7C0);
+        clear!(0x7C0);
+    }
+
+    #[allow(dead_code)]
+    mod raw_rv32 {
+        read_csr_rv32!(0x7C1);
+        write_csr_rv32!(0x7C1);
+    }
+
+    #[allow(dead_code)]
+    mod typed {
+        #[derive(Clone, Copy, Debug)]
+        pub struct Custom {
+            bits: usize,
+        }
+
+        read_csr_as!(Custom, 0x7C2);
+        write_csr!(0x7C2);
+        set!(0x7C2);
+        clear!(0x7C2);
+    }
+
+    #[allow(dead_code)]
+    mod table {
+        crate::csr! {
+            /// Custom register
+            Custom: 0x7C3, read, write, set, clear;
+
+            /// Enable
+            enable: 0, set_enable, clear_enable;
+        }
+    }
+
+    #[test]
+    fn expansions() {
+        let _: unsafe fn(usize) -> usize = raw::swap;
+        let _: unsafe fn(usize) -> usize = raw::read_set;
+        let _: unsafe fn(usize) -> usize = raw::read_clear;
+        let _: unsafe fn(usize) -> usize = raw_rv32::swap;
+        let _: unsafe fn(usize) -> typed::Custom = typed::swap;
+        let _: unsafe fn(usize) -> typed::Custom = typed::read_set;
+        let _: unsafe fn(usize) -> typed::Custom = typed::read_clear;
+        let _: unsafe fn(usize) = table::write;
+        let _: unsafe fn(usize) -> table::Custom = table::swap;
+        let _: unsafe fn(usize) -> table::Custom = table::read_set;
+        let _: unsafe fn(usize) -> table::Custom = table::read_clear;
+    }
+
+    #[test]
+    fn built_in() {
+        use crate::register::*;
+
+        let _: unsafe fn(usize) -> usize = mepc::swap;
+        let _: unsafe fn(usize) -> usize = sepc::swap;
+        let _: unsafe fn(usize) -> mtvec::Mtvec = mtvec::swap;
+        let _: unsafe fn(usize) -> stvec::Stvec = stvec::swap;
+        let _: unsafe fn(usize) -> satp::Satp = satp::swap;
+        let _: unsafe fn(usize) -> medeleg::Medeleg = medeleg::read_set;
+        let _: unsafe fn(usize) -> mideleg::Mideleg = mideleg::read_clear;
+        let _: unsafe fn(usize) -> mcounteren::Mcounteren = mcounteren::swap;
+        let _: unsafe fn(usize) -> menvcfg::Menvcfg = menvcfg::read_set;
+        let _: unsafe fn(usize) -> scause::Scause = scause::swap;
+        let _: unsafe fn(usize) -> usize = stval::swap;
+        let _: unsafe fn(usize) -> mstatus::Mstatus = mstatus::read_set;
+        let _: unsafe fn(usize) = mstatus::write;
+        let _: unsafe fn(usize) = sstatus::write;
+        let _: unsafe fn(usize) -> usize = mscratch::swap;
+        let _: unsafe fn(usize) -> usize = pmpaddr0::swap;
+    }
+}
@@ -71,7 +71,9 @@ impl Menvcfg {
 }
 
 read_csr_as!(Menvcfg, 0x30A);
+try_read_csr_as!(Menvcfg, 0x30A);
 write_csr!(0x30A);
+try_write_csr!(0x30A);
 set!(0x30A);
 clear!(0x30A);
 
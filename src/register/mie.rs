@@ -1,103 +1,38 @@
 //! mie register
 
-use bit_field::BitField;
-
-/// mie register
-#[derive(Clone, Copy, Debug)]
-pub struct Mie {
-    bits: usize,
-}
-
-impl Mie {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// mie register
+    Mie: 0x304, read, set, clear;
 
     /// User Software Interrupt Enable
-    #[inline]
-    pub fn usoft(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    usoft: 0, set_usoft, clear_usoft;
     /// Supervisor Software Interrupt Enable
-    #[inline]
-    pub fn ssoft(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    ssoft: 1, set_ssoft, clear_ssoft;
     /// Machine Software Interrupt Enable
-    #[inline]
-    pub fn msoft(&self) -> bool {
-        self.bits.get_bit(3)
-    }
-
+    msoft: 3, set_msoft, clear_msoft;
     /// User Timer Interrupt Enable
-    #[inline]
-    pub fn utimer(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    utimer: 4, set_utimer, clear_utimer;
     /// Supervisor Timer Interrupt Enable
-    #[inline]
-    pub fn stimer(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    stimer: 5, set_stimer, clear_stimer;
     /// Machine Timer Interrupt Enable
-    #[inline]
-    pub fn mtimer(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-
+    mtimer: 7, set_mtimer, clear_mtimer;
     /// User External Interrupt Enable
-    #[inline]
-    pub fn uext(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-
+    uext: 8, set_uext, clear_uext;
     /// Supervisor External Interrupt Enable
-    #[inline]
-    pub fn sext(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-
+    sext: 9, set_sext, clear_sext;
     /// Machine External Interrupt Enable
-    #[inline]
-    pub fn mext(&self) -> bool {
-        self.bits.get_bit(11)
-    }
+    mext: 11, set_mext, clear_mext;
 }
 
-read_csr_as!(Mie, 0x304);
-set!(0x304);
-clear!(0x304);
-
-set_clear_csr!(
+read_set_clear_csr_imm!(Mie, 0x304,
     /// User Software Interrupt Enable
-    , set_usoft, clear_usoft, 1 << 0);
-set_clear_csr!(
+    , read_set_usoft, read_clear_usoft, 1 << 0);
+read_set_clear_csr_imm!(Mie, 0x304,
     /// Supervisor Software Interrupt Enable
-    , set_ssoft, clear_ssoft, 1 << 1);
-set_clear_csr!(
+    , read_set_ssoft, read_clear_ssoft, 1 << 1);
+read_set_clear_csr_imm!(Mie, 0x304,
     /// Machine Software Interrupt Enable
-    , set_msoft, clear_msoft, 1 << 3);
-set_clear_csr!(
+    , read_set_msoft, read_clear_msoft, 1 << 3);
+read_set_clear_csr_imm!(Mie, 0x304,
     /// User Timer Interrupt Enable
-    , set_utimer, clear_utimer, 1 << 4);
-set_clear_csr!(
-    /// Supervisor Timer Interrupt Enable
-    , set_stimer, clear_stimer, 1 << 5);
-set_clear_csr!(
-    /// Machine Timer Interrupt Enable
-    , set_mtimer, clear_mtimer, 1 << 7);
-set_clear_csr!(
-    /// User External Interrupt Enable
-    , set_uext, clear_uext, 1 << 8);
-set_clear_csr!(
-    /// Supervisor External Interrupt Enable
-    , set_sext, clear_sext, 1 << 9);
-set_clear_csr!(
-    /// Machine External Interrupt Enable
-    , set_mext, clear_mext, 1 << 11);
+    , read_set_utimer, read_clear_utimer, 1 << 4);
@@ -1,94 +1,35 @@
 //! mip register
 
-use bit_field::BitField;
-
-/// mip register
-#[derive(Clone, Copy, Debug)]
-pub struct Mip {
-    bits: usize,
-}
-
-impl Mip {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// mip register
+    Mip: 0x344, read, set, clear;
 
     /// User Software Interrupt Pending
-    #[inline]
-    pub fn usoft(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    usoft: 0, set_usoft, clear_usoft;
     /// Supervisor Software Interrupt Pending
-    #[inline]
-    pub fn ssoft(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    ssoft: 1, set_ssoft, clear_ssoft;
     /// Machine Software Interrupt Pending
-    #[inline]
-    pub fn msoft(&self) -> bool {
-        self.bits.get_bit(3)
-    }
-
+    msoft: 3;
     /// User Timer Interrupt Pending
-    #[inline]
-    pub fn utimer(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    utimer: 4, set_utimer, clear_utimer;
     /// Supervisor Timer Interrupt Pending
-    #[inline]
-    pub fn stimer(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    stimer: 5, set_stimer, clear_stimer;
     /// Machine Timer Interrupt Pending
-    #[inline]
-    pub fn mtimer(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-
+    mtimer: 7;
     /// User External Interrupt Pending
-    #[inline]
-    pub fn uext(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-
+    uext: 8, set_uext, clear_uext;
     /// Supervisor External Interrupt Pending
-    #[inline]
-    pub fn sext(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-
+    sext: 9, set_sext, clear_sext;
     /// Machine External Interrupt Pending
-    #[inline]
-    pub fn mext(&self) -> bool {
-        self.bits.get_bit(11)
-    }
+    mext: 11;
 }
 
-read_csr_as!(Mip, 0x344);
-set!(0x344);
-clear!(0x344);
-
-set_clear_csr!(
+read_set_clear_csr_imm!(Mip, 0x344,
     /// User Software Interrupt Pending
-    , set_usoft, clear_usoft, 1 << 0);
-set_clear_csr!(
+    , read_set_usoft, read_clear_usoft, 1 << 0);
+read_set_clear_csr_imm!(Mip, 0x344,
     /// Supervisor Software Interrupt Pending
-    , set_ssoft, clear_ssoft, 1 << 1);
-set_clear_csr!(
+    , read_set_ssoft, read_clear_ssoft, 1 << 1);
+read_set_clear_csr_imm!(Mip, 0x344,
     /// User Timer Interrupt Pending
-    , set_utimer, clear_utimer, 1 << 4);
-set_clear_csr!(
-    /// Supervisor Timer Interrupt Pending
-    , set_stimer, clear_stimer, 1 << 5);
-set_clear_csr!(
-    /// User External Interrupt Pending
-    , set_uext, clear_uext, 1 << 8);
-set_clear_csr!(
-    /// Supervisor External Interrupt Pending
-    , set_sext, clear_sext, 1 << 9);
+    , read_set_utimer, read_clear_utimer, 1 << 4);
@@ -14,6 +14,11 @@
 #[macro_use]
 mod macros;
 
+pub mod probe;
+
+mod dynamic;
+pub use self::dynamic::*;
+
 // User Trap Setup
 pub mod uie;
 pub mod ustatus;
@@ -27,8 +32,28 @@ pub mod uscratch;
 pub mod utval;
 
 // User Floating-Point CSRs
-// TODO: frm, fflags
 pub mod fcsr;
+pub mod fflags;
+pub mod frm;
+
+// User Vector CSRs
+#[cfg(target_feature = "v")]
+pub mod vcsr;
+#[cfg(target_feature = "v")]
+pub mod vl;
+#[cfg(target_feature = "v")]
+pub mod vlenb;
+#[cfg(target_feature = "v")]
+pub mod vstart;
+#[cfg(target_feature = "v")]
+pub mod vtype;
+#[cfg(target_feature = "v")]
+pub mod vxrm;
+#[cfg(target_feature = "v")]
+pub mod vxsat;
+
+// Entropy Source
+pub mod seed;
 
 // User Counter/Timers
 pub mod cycle;
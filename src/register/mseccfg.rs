@@ -1,49 +1,24 @@
 //! mseccfg register
 
-use bit_field::BitField;
-
-/// mseccfg register
-#[derive(Clone, Copy, Debug)]
-pub struct Mseccfg {
-    bits: usize,
-}
-
-impl Mseccfg {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-
+csr! {
+    /// mseccfg register
+    Mseccfg: 0x747, read, set, clear;
+
+    /// S-mode Seed Access
+    ///
+    /// Allows S-mode to access the `seed` CSR.
+    sseed: 9, set_sseed, clear_sseed;
+    /// U-mode Seed Access
+    ///
+    /// Allows U-mode to access the `seed` CSR.
+    useed: 8, set_useed, clear_useed;
     /// Rule Locking Bypass
-    #[inline]
-    pub fn rlb(&self) -> bool {
-        self.bits.get_bit(2)
-    }
-
+    rlb: 2, set_rlb, clear_rlb;
     /// Machine Mode Whitelist Policy
-    #[inline]
-    pub fn mmwp(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    mmwp: 1, set_mmwp, clear_mmwp;
     /// Machine Mode Lockdown
-    #[inline]
-    pub fn mml(&self) -> bool {
-        self.bits.get_bit(0)
-    }
+    mml: 0, set_mml, clear_mml;
 }
 
-read_csr_as!(Mseccfg, 0x747);
-set!(0x747);
-clear!(0x747);
-
-set_clear_csr!(
-    /// Rule Locking Bypass 
-    , set_rlb, clear_rlb, 1 << 2);
-set_clear_csr!(
-    /// Machine Mode Whitelist Policy 
-    , set_mmwp, clear_mmwp, 1 << 1);
-set_clear_csr!(
-    /// Machine Mode Lockdown 
-    , set_mml, clear_mml, 1 << 0);
+try_read_csr_as!(Mseccfg, 0x747);
+try_set_clear_csr!(0x747);
@@ -7,170 +7,51 @@
 // FIXME: `SXL` and `UXL` bits require a structure interpreting XLEN,
 // which would be the best way we implement this using Rust?
 
-use bit_field::BitField;
-use core::mem::size_of;
+csr! {
+    /// mstatus register
+    Mstatus: 0x300, read, write, set, clear;
 
-/// mstatus register
-#[derive(Clone, Copy, Debug)]
-pub struct Mstatus {
-    bits: usize,
-}
-
-/// Additional extension state
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum XS {
-    /// All off
-    AllOff = 0,
-
-    /// None dirty or clean, some on
-    NoneDirtyOrClean = 1,
-
-    /// None dirty, some clean
-    NoneDirtySomeClean = 2,
-
-    /// Some dirty
-    SomeDirty = 3,
-}
-
-/// Floating-point extension state
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum FS {
-    Off = 0,
-    Initial = 1,
-    Clean = 2,
-    Dirty = 3,
-}
-
-/// Machine Previous Privilege Mode
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum MPP {
-    Machine = 3,
-    Supervisor = 1,
-    User = 0,
-}
-
-/// Supervisor Previous Privilege Mode
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum SPP {
-    Supervisor = 1,
-    User = 0,
-}
-
-impl Mstatus {
     /// User Interrupt Enable
-    #[inline]
-    pub fn uie(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    uie: 0, set_uie, clear_uie;
     /// Supervisor Interrupt Enable
-    #[inline]
-    pub fn sie(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    sie: 1, set_sie, clear_sie;
     /// Machine Interrupt Enable
-    #[inline]
-    pub fn mie(&self) -> bool {
-        self.bits.get_bit(3)
-    }
-
+    mie: 3, set_mie, clear_mie;
     /// User Previous Interrupt Enable
-    #[inline]
-    pub fn upie(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    upie: 4;
     /// Supervisor Previous Interrupt Enable
-    #[inline]
-    pub fn spie(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    spie: 5;
     /// Machine Previous Interrupt Enable
-    #[inline]
-    pub fn mpie(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-
+    mpie: 7;
     /// Supervisor Previous Privilege Mode
-    #[inline]
-    pub fn spp(&self) -> SPP {
-        match self.bits.get_bit(8) {
-            true => SPP::Supervisor,
-            false => SPP::User,
-        }
-    }
-
-    /// Machine Previous Privilege Mode
-    #[inline]
-    pub fn mpp(&self) -> MPP {
-        match self.bits.get_bits(11..13) {
-            0b00 => MPP::User,
-            0b01 => MPP::Supervisor,
-            0b11 => MPP::Machine,
-            _ => unreachable!(),
-        }
-    }
-
+    spp: 8..9 as SPP;
+    /// Vector extension state
+    ///
+    /// Encodes the status of the vector unit, including the vector registers and CSRs.
+    #[cfg(target_feature = "v")]
+    vs: 9..11 as FS, set_vs;
     /// Floating-point extension state
     ///
     /// Encodes the status of the floating-point unit,
     /// including the CSR `fcsr` and floating-point data registers `f0–f31`.
-    #[inline]
-    pub fn fs(&self) -> FS {
-        match self.bits.get_bits(13..15) {
-            0b00 => FS::Off,
-            0b01 => FS::Initial,
-            0b10 => FS::Clean,
-            0b11 => FS::Dirty,
-            _ => unreachable!(),
-        }
-    }
-
+    fs: 13..15 as FS, set_fs;
     /// Additional extension state
     ///
     /// Encodes the status of additional user-mode extensions and associated state.
-    #[inline]
-    pub fn xs(&self) -> XS {
-        match self.bits.get_bits(15..17) {
-            0b00 => XS::AllOff,
-            0b01 => XS::NoneDirtyOrClean,
-            0b10 => XS::NoneDirtySomeClean,
-            0b11 => XS::SomeDirty,
-            _ => unreachable!(),
-        }
-    }
-
+    xs: 15..17 as XS;
     /// Modify Memory PRiVilege
-    #[inline]
-    pub fn mprv(&self) -> bool {
-        self.bits.get_bit(17)
-    }
-
+    mprv: 17, set_mprv, clear_mprv;
     /// Permit Supervisor User Memory access
-    #[inline]
-    pub fn sum(&self) -> bool {
-        self.bits.get_bit(18)
-    }
-
+    sum: 18, set_sum, clear_sum;
     /// Make eXecutable Readable
-    #[inline]
-    pub fn mxr(&self) -> bool {
-        self.bits.get_bit(19)
-    }
-
+    mxr: 19, set_mxr, clear_mxr;
     /// Trap Virtual Memory
     ///
     /// If this bit is set, reads or writes to `satp` CSR or execute `sfence.vma`
     /// instruction when in S-mode will raise an illegal instruction exception.
     ///
     /// TVM is hard-wired to 0 when S-mode is not supported.
-    #[inline]
-    pub fn tvm(&self) -> bool {
-        self.bits.get_bit(20)
-    }
-
+    tvm: 20, set_tvm, clear_tvm;
     /// Timeout Wait
     ///
     /// Indicates that if WFI instruction should be intercepted.
@@ -180,21 +61,14 @@ impl Mstatus {
     /// an illegal instruction trap; or could always cause trap then the time limit is zero.
     ///
     /// TW is hard-wired to 0 when S-mode is not supported.
-    #[inline]
-    pub fn tw(&self) -> bool {
-        self.bits.get_bit(21)
-    }
-
+    tw: 21, set_tw, clear_tw;
     /// Trap SRET
     ///
     /// Indicates that if SRET instruction should be trapped to raise illegal
     /// instruction exception.
     ///
     /// If S-mode is not supported, TSR bit is hard-wired to 0.
-    #[inline]
-    pub fn tsr(&self) -> bool {
-        self.bits.get_bit(22)
-    }
+    tsr: 22, set_tsr, clear_tsr;
 
     /*
         FIXME: There are MBE and SBE bits in 1.12; once Privileged Specification version 1.12
@@ -203,26 +77,72 @@ impl Mstatus {
 
     /// Whether either the FS field or XS field
     /// signals the presence of some dirty state
-    #[inline]
-    pub fn sd(&self) -> bool {
-        self.bits.get_bit(size_of::<usize>() * 8 - 1)
+    #[cfg(target_pointer_width = "32")]
+    sd: 31;
+    /// Whether either the FS field or XS field
+    /// signals the presence of some dirty state
+    #[cfg(target_pointer_width = "64")]
+    sd: 63;
+
+    /// Additional extension state
+    pub enum XS {
+        /// All off
+        AllOff = 0,
+
+        /// None dirty or clean, some on
+        NoneDirtyOrClean = 1,
+
+        /// None dirty, some clean
+        NoneDirtySomeClean = 2,
+
+        /// Some dirty
+        SomeDirty = 3,
+    }
+
+    /// Floating-point extension state
+    pub enum FS {
+        Off = 0,
+        Initial = 1,
+        Clean = 2,
+        Dirty = 3,
+    }
+
+    /// Machine Previous Privilege Mode
+    pub enum MPP {
+        Machine = 3,
+        Supervisor = 1,
+        User = 0,
+    }
+
+    /// Supervisor Previous Privilege Mode
+    pub enum SPP {
+        Supervisor = 1,
+        User = 0,
     }
 }
 
-read_csr_as!(Mstatus, 0x300);
-write_csr!(0x300);
-set!(0x300);
-clear!(0x300);
+impl Mstatus {
+    /// Machine Previous Privilege Mode
+    #[inline]
+    pub fn mpp(&self) -> MPP {
+        match MPP::from_bits((self.bits >> 11) & 0b11) {
+            Some(mpp) => mpp,
+            // The reserved value `0b10` is never held by this WARL field
+            None => unreachable!(),
+        }
+    }
+}
 
-set_clear_csr!(
+read_set_clear_csr_imm!(Mstatus, 0x300,
     /// User Interrupt Enable
-    , set_uie, clear_uie, 1 << 0);
-set_clear_csr!(
+    , read_set_uie, read_clear_uie, 1 << 0);
+read_set_clear_csr_imm!(Mstatus, 0x300,
     /// Supervisor Interrupt Enable
-    , set_sie, clear_sie, 1 << 1);
-set_clear_csr!(
+    , read_set_sie, read_clear_sie, 1 << 1);
+read_set_clear_csr_imm!(Mstatus, 0x300,
     /// Machine Interrupt Enable
-    , set_mie, clear_mie, 1 << 3);
+    , read_set_mie, read_clear_mie, 1 << 3);
+
 set_csr!(
     /// User Previous Interrupt Enable
     , set_upie, 1 << 4);
@@ -232,24 +152,6 @@ set_csr!(
 set_csr!(
     /// Machine Previous Interrupt Enable
     , set_mpie, 1 << 7);
-set_clear_csr!(
-    /// Modify Memory PRiVilege
-    , set_mprv, clear_mprv, 1 << 17);
-set_clear_csr!(
-    /// Permit Supervisor User Memory access
-    , set_sum, clear_sum, 1 << 18);
-set_clear_csr!(
-    /// Make eXecutable Readable
-    , set_mxr, clear_mxr, 1 << 19);
-set_clear_csr!(
-    /// Trap Virtual Memory
-    , set_tvm, clear_tvm, 1 << 20);
-set_clear_csr!(
-    /// Timeout Wait
-    , set_tw, clear_tw, 1 << 21);
-set_clear_csr!(
-    /// Trap SRET
-    , set_tsr, clear_tsr, 1 << 22);
 
 /// Supervisor Previous Privilege Mode
 #[inline]
@@ -261,17 +163,11 @@ pub unsafe fn set_spp(spp: SPP) {
 }
 
 /// Machine Previous Privilege Mode
+///
+/// # Safety
+///
+/// Changing the CSR may break the assumptions of the running code.
 #[inline]
 pub unsafe fn set_mpp(mpp: MPP) {
-    let mut value = _read();
-    value.set_bits(11..13, mpp as usize);
-    _write(value);
-}
-
-/// Floating-point extension state
-#[inline]
-pub unsafe fn set_fs(fs: FS) {
-    let mut value = _read();
-    value.set_bits(13..15, fs as usize);
-    _write(value);
+    _write((_read() & !(0b11 << 11)) | (mpp as usize) << 11);
 }
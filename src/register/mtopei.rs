@@ -7,33 +7,15 @@
 //! function, which will atomically claim the highest-priority pending
 //! interrupt and allow the interrupt handler to process it.
 
-use bit_field::BitField;
-
-/// mtopei register
-#[derive(Clone, Copy, Debug)]
-pub struct Mtopei {
-    bits: usize,
-}
-
-impl Mtopei {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// mtopei register
+    Mtopei: 0x35C, read;
 
     /// Interrupt identity
-    #[inline]
-    pub fn identity(&self) -> usize {
-        self.bits.get_bits(16..26)
-    }
-
+    identity: 16..26;
     /// Interrupt priority
-    #[inline]
-    pub fn priority(&self) -> usize {
-        self.bits.get_bits(0..10)
-    }
+    priority: 0..10;
 }
 
-read_csr_as!(Mtopei, 0x35C);
+try_read_csr_as!(Mtopei, 0x35C);
 claim_csr_as!(Mtopei, 0x35C);
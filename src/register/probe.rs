@@ -0,0 +1,47 @@
+//! Probing of optional CSRs
+//!
+//! Accessing a CSR that is not implemented raises an illegal instruction exception. The `try_`
+//! functions of the optional CSRs (`try_read`, `try_write`, ... of `mseccfg`, `menvcfg`,
+//! `stimecmp`, `mtopei`, ...) catch it instead: they mask the interrupts, point `mtvec` (`stvec`
+//! with `s-mode`) to the instruction following the access, and restore `mtvec`, `mepc`, `mcause`,
+//! `mtval` and `mstatus` afterwards, so that the probe is invisible to the running code, even in a
+//! trap handler.
+//!
+//! With `s-mode`, the exception must be delegated to S-mode (`medeleg`), as is done by the usual
+//! SBI implementations: otherwise it is handled by M-mode, which may not return.
+
+/// The CSR access raised an exception: the CSR is not implemented, or not accessible from the
+/// current privilege mode
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Unimplemented;
+
+/// Runs `access`, which returns `false` if the trap vector caught an exception, with the
+/// interrupts masked, and restores the trap CSRs overwritten by the exception
+#[cfg(riscv)]
+#[inline]
+pub(crate) unsafe fn catch(access: impl FnOnce() -> bool) -> bool {
+    let (status, epc, cause, tval): (usize, usize, usize, usize);
+    core::arch::asm!(
+        concat!("csrrci {status}, ", trap_csr!(status), ", ", trap_csr!(ie)),
+        concat!("csrr {epc}, ", trap_csr!(epc)),
+        concat!("csrr {cause}, ", trap_csr!(cause)),
+        concat!("csrr {tval}, ", trap_csr!(tval)),
+        status = out(reg) status,
+        epc = out(reg) epc,
+        cause = out(reg) cause,
+        tval = out(reg) tval,
+    );
+    let ok = access();
+    if !ok {
+        core::arch::asm!(
+            concat!("csrw ", trap_csr!(epc), ", {epc}"),
+            concat!("csrw ", trap_csr!(cause), ", {cause}"),
+            concat!("csrw ", trap_csr!(tval), ", {tval}"),
+            epc = in(reg) epc,
+            cause = in(reg) cause,
+            tval = in(reg) tval,
+        );
+    }
+    core::arch::asm!(concat!("csrw ", trap_csr!(status), ", {0}"), in(reg) status);
+    ok
+}
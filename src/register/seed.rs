@@ -0,0 +1,76 @@
+//! seed register
+//!
+//! Entropy source of the Zkr extension. Each read returns the state of the source and, when it is
+//! `ES16`, 16 bits of raw entropy that must be conditioned (see the `entropy` module) before being
+//! used as random numbers. Reading the same sample twice is not possible: the CSR can only be
+//! accessed with a read-write instruction, which discards the sample.
+//!
+//! S-mode and U-mode access is enabled by `mseccfg.SSEED` and `mseccfg.USEED`.
+
+use bit_field::BitField;
+
+/// seed register
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Seed {
+    bits: usize,
+}
+
+/// Status of the entropy source
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Opst {
+    /// Built-in self test on power-up or reset; no entropy available yet
+    Bist = 0b00,
+    /// Entropy is being generated, try again later
+    Wait = 0b01,
+    /// 16 bits of entropy are available
+    Es16 = 0b10,
+    /// Unrecoverable self-test error
+    Dead = 0b11,
+}
+
+impl Seed {
+    /// Constructs the register from raw bits
+    #[inline]
+    pub const fn from_bits(bits: usize) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the contents of the register as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Status of the entropy source
+    #[inline]
+    pub fn opst(&self) -> Opst {
+        match self.bits.get_bits(30..32) {
+            0b00 => Opst::Bist,
+            0b01 => Opst::Wait,
+            0b10 => Opst::Es16,
+            0b11 => Opst::Dead,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Raw entropy sample, or `None` if the status is not `ES16`
+    #[inline]
+    pub fn entropy(&self) -> Option<u16> {
+        match self.opst() {
+            Opst::Es16 => Some(self.bits.get_bits(0..16) as u16),
+            _ => None,
+        }
+    }
+}
+
+swap!(0x015);
+
+/// Polls the entropy source
+///
+/// Raises an illegal instruction exception outside M-mode, unless allowed by `mseccfg`.
+#[inline]
+pub fn read() -> Seed {
+    Seed {
+        bits: unsafe { _swap(0) },
+    }
+}
@@ -1,76 +1,29 @@
 //! sie register
 
-use bit_field::BitField;
-
-/// sie register
-#[derive(Clone, Copy, Debug)]
-pub struct Sie {
-    bits: usize,
-}
-
-impl Sie {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// sie register
+    Sie: 0x104, read, set, clear;
 
     /// User Software Interrupt Enable
-    #[inline]
-    pub fn usoft(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    usoft: 0, set_usoft, clear_usoft;
     /// Supervisor Software Interrupt Enable
-    #[inline]
-    pub fn ssoft(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    ssoft: 1, set_ssoft, clear_ssoft;
     /// User Timer Interrupt Enable
-    #[inline]
-    pub fn utimer(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    utimer: 4, set_utimer, clear_utimer;
     /// Supervisor Timer Interrupt Enable
-    #[inline]
-    pub fn stimer(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    stimer: 5, set_stimer, clear_stimer;
     /// User External Interrupt Enable
-    #[inline]
-    pub fn uext(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-
+    uext: 8, set_uext, clear_uext;
     /// Supervisor External Interrupt Enable
-    #[inline]
-    pub fn sext(&self) -> bool {
-        self.bits.get_bit(9)
-    }
+    sext: 9, set_sext, clear_sext;
 }
 
-read_csr_as!(Sie, 0x104);
-set!(0x104);
-clear!(0x104);
-
-set_clear_csr!(
+read_set_clear_csr_imm!(Sie, 0x104,
     /// User Software Interrupt Enable
-    , set_usoft, clear_usoft, 1 << 0);
-set_clear_csr!(
+    , read_set_usoft, read_clear_usoft, 1 << 0);
+read_set_clear_csr_imm!(Sie, 0x104,
     /// Supervisor Software Interrupt Enable
-    , set_ssoft, clear_ssoft, 1 << 1);
-set_clear_csr!(
+    , read_set_ssoft, read_clear_ssoft, 1 << 1);
+read_set_clear_csr_imm!(Sie, 0x104,
     /// User Timer Interrupt Enable
-    , set_utimer, clear_utimer, 1 << 4);
-set_clear_csr!(
-    /// Supervisor Timer Interrupt Enable
-    , set_stimer, clear_stimer, 1 << 5);
-set_clear_csr!(
-    /// User External Interrupt Enable
-    , set_uext, clear_uext, 1 << 8);
-set_clear_csr!(
-    /// Supervisor External Interrupt Enable
-    , set_sext, clear_sext, 1 << 9);
+    , read_set_utimer, read_clear_utimer, 1 << 4);
@@ -1,55 +1,19 @@
 //! sip register
 
-use bit_field::BitField;
-
-/// sip register
-#[derive(Clone, Copy, Debug)]
-pub struct Sip {
-    bits: usize,
-}
-
-impl Sip {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// sip register
+    Sip: 0x144, read;
 
     /// User Software Interrupt Pending
-    #[inline]
-    pub fn usoft(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    usoft: 0;
     /// Supervisor Software Interrupt Pending
-    #[inline]
-    pub fn ssoft(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    ssoft: 1;
     /// User Timer Interrupt Pending
-    #[inline]
-    pub fn utimer(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    utimer: 4;
     /// Supervisor Timer Interrupt Pending
-    #[inline]
-    pub fn stimer(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    stimer: 5;
     /// User External Interrupt Pending
-    #[inline]
-    pub fn uext(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-
+    uext: 8;
     /// Supervisor External Interrupt Pending
-    #[inline]
-    pub fn sext(&self) -> bool {
-        self.bits.get_bit(9)
-    }
+    sext: 9;
 }
-
-read_csr_as!(Sip, 0x144);
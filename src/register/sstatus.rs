@@ -1,124 +1,62 @@
 //! sstatus register
 
 pub use super::mstatus::FS;
-use bit_field::BitField;
-use core::mem::size_of;
 
-/// Supervisor Status Register
-#[derive(Clone, Copy, Debug)]
-pub struct Sstatus {
-    bits: usize,
-}
+csr! {
+    /// Supervisor Status Register
+    Sstatus: 0x100, read, write, set, clear;
 
-/// Supervisor Previous Privilege Mode
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum SPP {
-    Supervisor = 1,
-    User = 0,
-}
-
-impl Sstatus {
     /// User Interrupt Enable
-    #[inline]
-    pub fn uie(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-
+    uie: 0, set_uie, clear_uie;
     /// Supervisor Interrupt Enable
-    #[inline]
-    pub fn sie(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-
+    sie: 1, set_sie, clear_sie;
     /// User Previous Interrupt Enable
-    #[inline]
-    pub fn upie(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-
+    upie: 4;
     /// Supervisor Previous Interrupt Enable
-    #[inline]
-    pub fn spie(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-
+    spie: 5;
     /// Supervisor Previous Privilege Mode
-    #[inline]
-    pub fn spp(&self) -> SPP {
-        match self.bits.get_bit(8) {
-            true => SPP::Supervisor,
-            false => SPP::User,
-        }
-    }
-
+    spp: 8..9 as SPP;
+    /// The status of the vector unit
+    #[cfg(target_feature = "v")]
+    vs: 9..11 as FS, set_vs;
     /// The status of the floating-point unit
-    #[inline]
-    pub fn fs(&self) -> FS {
-        match self.bits.get_bits(13..15) {
-            0 => FS::Off,
-            1 => FS::Initial,
-            2 => FS::Clean,
-            3 => FS::Dirty,
-            _ => unreachable!(),
-        }
-    }
-
+    fs: 13..15 as FS, set_fs;
     /// The status of additional user-mode extensions
     /// and associated state
-    #[inline]
-    pub fn xs(&self) -> FS {
-        match self.bits.get_bits(15..17) {
-            0 => FS::Off,
-            1 => FS::Initial,
-            2 => FS::Clean,
-            3 => FS::Dirty,
-            _ => unreachable!(),
-        }
-    }
-
+    xs: 15..17 as FS;
     /// Permit Supervisor User Memory access
-    #[inline]
-    pub fn sum(&self) -> bool {
-        self.bits.get_bit(18)
-    }
-
+    sum: 18, set_sum, clear_sum;
     /// Make eXecutable Readable
-    #[inline]
-    pub fn mxr(&self) -> bool {
-        self.bits.get_bit(19)
-    }
-
+    mxr: 19, set_mxr, clear_mxr;
+    /// Whether either the FS field or XS field
+    /// signals the presence of some dirty state
+    #[cfg(target_pointer_width = "32")]
+    sd: 31;
     /// Whether either the FS field or XS field
     /// signals the presence of some dirty state
-    #[inline]
-    pub fn sd(&self) -> bool {
-        self.bits.get_bit(size_of::<usize>() * 8 - 1)
+    #[cfg(target_pointer_width = "64")]
+    sd: 63;
+
+    /// Supervisor Previous Privilege Mode
+    pub enum SPP {
+        Supervisor = 1,
+        User = 0,
     }
 }
 
-read_csr_as!(Sstatus, 0x100);
-write_csr!(0x100);
-set!(0x100);
-clear!(0x100);
-
-set_clear_csr!(
+read_set_clear_csr_imm!(Sstatus, 0x100,
     /// User Interrupt Enable
-    , set_uie, clear_uie, 1 << 0);
-set_clear_csr!(
+    , read_set_uie, read_clear_uie, 1 << 0);
+read_set_clear_csr_imm!(Sstatus, 0x100,
     /// Supervisor Interrupt Enable
-    , set_sie, clear_sie, 1 << 1);
+    , read_set_sie, read_clear_sie, 1 << 1);
+
 set_csr!(
     /// User Previous Interrupt Enable
     , set_upie, 1 << 4);
 set_csr!(
     /// Supervisor Previous Interrupt Enable
     , set_spie, 1 << 5);
-set_clear_csr!(
-    /// Permit Supervisor User Memory access
-    , set_sum, clear_sum, 1 << 18);
-set_clear_csr!(
-    /// Make eXecutable Readable
-    , set_mxr, clear_mxr, 1 << 19);
 
 /// Supervisor Previous Privilege Mode
 #[inline]
@@ -128,11 +66,3 @@ pub unsafe fn set_spp(spp: SPP) {
         SPP::User => _clear(1 << 8),
     }
 }
-
-/// The status of the floating-point unit
-#[inline]
-pub unsafe fn set_fs(fs: FS) {
-    let mut value = _read();
-    value.set_bits(13..15, fs as usize);
-    _write(value);
-}
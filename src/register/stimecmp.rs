@@ -2,4 +2,9 @@
 
 read_csr_as_usize!(0x14D);
 write_csr_as_usize!(0x14D);
+try_read_csr_as_usize!(0x14D);
+try_write_csr_as_usize!(0x14D);
 read_composite_csr!(super::stimecmph::read(), read());
+write_composite_csr!(super::stimecmph::write, write);
+try_read_composite_csr!(super::stimecmph::try_read(), try_read());
+try_write_composite_csr!(super::stimecmph::try_write, try_write);
@@ -2,3 +2,5 @@
 
 read_csr_as_usize_rv32!(0x15D);
 write_csr_as_usize_rv32!(0x15D);
+try_read_csr_as_usize_rv32!(0x15D);
+try_write_csr_as_usize_rv32!(0x15D);
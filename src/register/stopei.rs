@@ -7,33 +7,15 @@
 //! function, which will atomically claim the highest-priority pending
 //! interrupt and allow the interrupt handler to process it.
 
-use bit_field::BitField;
-
-/// stopei register
-#[derive(Clone, Copy, Debug)]
-pub struct Stopei {
-    bits: usize,
-}
-
-impl Stopei {
-    /// Returns the contents of the register as raw bits
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
+csr! {
+    /// stopei register
+    Stopei: 0x15C, read;
 
     /// Interrupt identity
-    #[inline]
-    pub fn identity(&self) -> usize {
-        self.bits.get_bits(16..26)
-    }
-
+    identity: 16..26;
     /// Interrupt priority
-    #[inline]
-    pub fn priority(&self) -> usize {
-        self.bits.get_bits(0..10)
-    }
+    priority: 0..10;
 }
 
-read_csr_as!(Stopei, 0x15C);
+try_read_csr_as!(Stopei, 0x15C);
 claim_csr_as!(Stopei, 0x15C);
@@ -0,0 +1,47 @@
+//! vcsr register
+//!
+//! Vector control and status register, grouping `vxsat` and `vxrm`.
+
+use bit_field::BitField;
+
+pub use super::vxrm::FixedPointRoundingMode;
+
+/// Vector control and status register
+#[derive(Clone, Copy, Debug)]
+pub struct Vcsr {
+    bits: usize,
+}
+
+impl Vcsr {
+    /// Returns the contents of the register as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Fixed-point saturation flag
+    #[inline]
+    pub fn vxsat(&self) -> bool {
+        self.bits.get_bit(0)
+    }
+
+    /// Fixed-point rounding mode
+    #[inline]
+    pub fn vxrm(&self) -> FixedPointRoundingMode {
+        FixedPointRoundingMode::from_bits(self.bits.get_bits(1..3))
+    }
+}
+
+read_csr_as!(Vcsr, 0x00F);
+write_csr!(0x00F);
+
+/// Writes the CSR
+///
+/// # Safety
+///
+/// Changes the rounding of the fixed-point instructions executed by the running code, and may discard
+/// the saturation flag.
+#[inline]
+pub unsafe fn write(vxsat: bool, vxrm: FixedPointRoundingMode) {
+    _write(vxsat as usize | (vxrm as usize) << 1);
+}
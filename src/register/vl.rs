@@ -0,0 +1,5 @@
+//! vl register
+//!
+//! Number of elements updated by a vector instruction, set by `vsetvl` and its variants.
+
+read_csr_as_usize!(0xC20);
@@ -0,0 +1,11 @@
+//! vlenb register
+//!
+//! Length of a vector register in bytes.
+
+read_csr_as_usize!(0xC22);
+
+/// Length of a vector register in bits (`VLEN`)
+#[inline]
+pub fn vlen() -> usize {
+    read() * 8
+}
@@ -0,0 +1,18 @@
+//! vstart register
+//!
+//! Index of the first element to be executed by a vector instruction.
+
+read_csr_as_usize!(0x008);
+write_csr!(0x008);
+
+/// Writes the CSR
+///
+/// # Safety
+///
+/// A nonzero `vstart` makes the next vector instruction skip its leading elements, including the
+/// vector code generated by the compiler (e.g. for `memcpy` or `memset`), which then leaves part of
+/// its destination unwritten.
+#[inline]
+pub unsafe fn write(bits: usize) {
+    _write(bits)
+}
@@ -0,0 +1,149 @@
+//! vtype register
+//!
+//! Vector data type, set by `vsetvl` and its variants.
+
+use bit_field::BitField;
+
+/// Vector data type register
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Vtype {
+    bits: usize,
+}
+
+/// Selected element width
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Sew {
+    /// 8-bit elements (`e8`)
+    E8 = 0b000,
+    /// 16-bit elements (`e16`)
+    E16 = 0b001,
+    /// 32-bit elements (`e32`)
+    E32 = 0b010,
+    /// 64-bit elements (`e64`)
+    E64 = 0b011,
+}
+
+impl Sew {
+    /// Element width in bits
+    #[inline]
+    pub const fn bits(self) -> usize {
+        8 << self as usize
+    }
+}
+
+/// Vector register group multiplier
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Lmul {
+    /// One eighth of a register (`mf8`)
+    Mf8 = 0b101,
+    /// One quarter of a register (`mf4`)
+    Mf4 = 0b110,
+    /// Half a register (`mf2`)
+    Mf2 = 0b111,
+    /// One register (`m1`)
+    M1 = 0b000,
+    /// Groups of two registers (`m2`)
+    M2 = 0b001,
+    /// Groups of four registers (`m4`)
+    M4 = 0b010,
+    /// Groups of eight registers (`m8`)
+    M8 = 0b011,
+}
+
+impl Vtype {
+    /// Encodes a data type, as taken by `asm::vsetvl`
+    ///
+    /// `ta` and `ma` select the tail agnostic and mask agnostic policies.
+    #[inline]
+    pub const fn new(sew: Sew, lmul: Lmul, ta: bool, ma: bool) -> Self {
+        Self {
+            bits: lmul as usize | (sew as usize) << 3 | (ta as usize) << 6 | (ma as usize) << 7,
+        }
+    }
+
+    /// Constructs the data type from raw bits
+    #[inline]
+    pub const fn from_bits(bits: usize) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the contents of the register as raw bits
+    #[inline]
+    pub const fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Selected element width
+    ///
+    /// Returns `None` for the reserved encodings.
+    #[inline]
+    pub fn sew(&self) -> Option<Sew> {
+        match self.bits.get_bits(3..6) {
+            0b000 => Some(Sew::E8),
+            0b001 => Some(Sew::E16),
+            0b010 => Some(Sew::E32),
+            0b011 => Some(Sew::E64),
+            _ => None,
+        }
+    }
+
+    /// Vector register group multiplier
+    ///
+    /// Returns `None` for the reserved encoding.
+    #[inline]
+    pub fn lmul(&self) -> Option<Lmul> {
+        match self.bits.get_bits(0..3) {
+            0b101 => Some(Lmul::Mf8),
+            0b110 => Some(Lmul::Mf4),
+            0b111 => Some(Lmul::Mf2),
+            0b000 => Some(Lmul::M1),
+            0b001 => Some(Lmul::M2),
+            0b010 => Some(Lmul::M4),
+            0b011 => Some(Lmul::M8),
+            _ => None,
+        }
+    }
+
+    /// Vector tail agnostic
+    #[inline]
+    pub fn ta(&self) -> bool {
+        self.bits.get_bit(6)
+    }
+
+    /// Vector mask agnostic
+    #[inline]
+    pub fn ma(&self) -> bool {
+        self.bits.get_bit(7)
+    }
+
+    /// Illegal value
+    ///
+    /// Set by `vsetvl` when the requested data type is not supported; the other fields are then
+    /// zero.
+    #[inline]
+    pub fn vill(&self) -> bool {
+        self.bits.get_bit(usize::BITS as usize - 1)
+    }
+
+    /// Maximum number of elements of this data type for vector registers of `vlen` bits
+    ///
+    /// Returns `None` when the data type is illegal or reserved.
+    #[inline]
+    pub fn vlmax(&self, vlen: usize) -> Option<usize> {
+        if self.vill() {
+            return None;
+        }
+        let elements = vlen / self.sew()?.bits();
+        Some(match self.lmul()? {
+            Lmul::Mf8 => elements / 8,
+            Lmul::Mf4 => elements / 4,
+            Lmul::Mf2 => elements / 2,
+            Lmul::M1 => elements,
+            Lmul::M2 => elements * 2,
+            Lmul::M4 => elements * 4,
+            Lmul::M8 => elements * 8,
+        })
+    }
+}
+
+read_csr_as!(Vtype, 0xC21);
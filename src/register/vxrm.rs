@@ -0,0 +1,50 @@
+//! vxrm register
+//!
+//! Fixed-point rounding mode, also accessible through `vcsr`.
+
+read_csr!(0x00A);
+write_csr!(0x00A);
+
+/// Fixed-point rounding mode
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FixedPointRoundingMode {
+    /// Round-to-nearest-up (`rnu`)
+    RoundToNearestUp = 0b00,
+
+    /// Round-to-nearest-even (`rne`)
+    RoundToNearestEven = 0b01,
+
+    /// Round-down, i.e. truncate (`rdn`)
+    RoundDown = 0b10,
+
+    /// Round-to-odd, i.e. OR bits into the LSB (`rod`)
+    RoundToOdd = 0b11,
+}
+
+impl FixedPointRoundingMode {
+    #[inline]
+    pub(crate) fn from_bits(bits: usize) -> Self {
+        match bits & 0b11 {
+            0b00 => Self::RoundToNearestUp,
+            0b01 => Self::RoundToNearestEven,
+            0b10 => Self::RoundDown,
+            _ => Self::RoundToOdd,
+        }
+    }
+}
+
+/// Reads the CSR
+#[inline]
+pub fn read() -> FixedPointRoundingMode {
+    FixedPointRoundingMode::from_bits(unsafe { _read() })
+}
+
+/// Writes the CSR
+///
+/// # Safety
+///
+/// Changes the rounding of the fixed-point instructions executed by the running code.
+#[inline]
+pub unsafe fn write(vxrm: FixedPointRoundingMode) {
+    _write(vxrm as usize);
+}
@@ -0,0 +1,22 @@
+//! vxsat register
+//!
+//! Fixed-point saturation flag, also accessible through `vcsr`.
+
+read_csr!(0x009);
+write_csr!(0x009);
+
+/// Reads the CSR, returning whether a fixed-point instruction saturated its result
+#[inline]
+pub fn read() -> bool {
+    unsafe { _read() & 1 != 0 }
+}
+
+/// Writes the CSR
+///
+/// # Safety
+///
+/// Clearing the flag discards the saturations reported so far, which other code may be waiting for.
+#[inline]
+pub unsafe fn write(vxsat: bool) {
+    _write(vxsat as usize);
+}
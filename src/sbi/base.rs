@@ -0,0 +1,93 @@
+//! Base extension (EID #0x10)
+//!
+//! Every SBI implementation provides this extension; it is used to discover which other
+//! extensions are available.
+
+use super::ecall;
+
+/// Extension ID
+pub const EID: usize = 0x10;
+
+pub(crate) const GET_SPEC_VERSION: usize = 0;
+pub(crate) const GET_IMPL_ID: usize = 1;
+pub(crate) const GET_IMPL_VERSION: usize = 2;
+pub(crate) const PROBE_EXTENSION: usize = 3;
+pub(crate) const GET_MVENDORID: usize = 4;
+pub(crate) const GET_MARCHID: usize = 5;
+pub(crate) const GET_MIMPID: usize = 6;
+
+/// SBI specification version
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SpecVersion {
+    bits: usize,
+}
+
+impl SpecVersion {
+    /// Returns the contents of the version as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Major number of the specification
+    #[inline]
+    pub fn major(&self) -> usize {
+        (self.bits >> 24) & 0x7f
+    }
+
+    /// Minor number of the specification
+    #[inline]
+    pub fn minor(&self) -> usize {
+        self.bits & 0xff_ffff
+    }
+}
+
+#[inline]
+fn call(fid: usize, arg: usize) -> usize {
+    // The base extension is mandatory and its functions cannot fail.
+    unsafe { ecall(EID, fid, [arg, 0, 0, 0, 0, 0]) }.value
+}
+
+/// Returns the version of the SBI specification implemented by the SEE
+#[inline]
+pub fn spec_version() -> SpecVersion {
+    SpecVersion {
+        bits: call(GET_SPEC_VERSION, 0),
+    }
+}
+
+/// Returns the SBI implementation ID
+#[inline]
+pub fn impl_id() -> usize {
+    call(GET_IMPL_ID, 0)
+}
+
+/// Returns the SBI implementation version
+#[inline]
+pub fn impl_version() -> usize {
+    call(GET_IMPL_VERSION, 0)
+}
+
+/// Returns `true` if the extension `eid` is available
+#[inline]
+pub fn probe_extension(eid: usize) -> bool {
+    call(PROBE_EXTENSION, eid) != 0
+}
+
+/// Returns the value of `mvendorid`
+#[inline]
+pub fn mvendorid() -> usize {
+    call(GET_MVENDORID, 0)
+}
+
+/// Returns the value of `marchid`
+#[inline]
+pub fn marchid() -> usize {
+    call(GET_MARCHID, 0)
+}
+
+/// Returns the value of `mimpid`
+#[inline]
+pub fn mimpid() -> usize {
+    call(GET_MIMPID, 0)
+}
@@ -0,0 +1,87 @@
+//! Collaborative Processor Performance Control extension (EID #0x43505043 "CPPC")
+//!
+//! Provides access to the ACPI CPPC registers of the calling hart, used to request performance
+//! levels (e.g. for DVFS).
+
+use super::{ecall, split_u64, Error};
+
+/// Extension ID
+pub const EID: usize = 0x43505043;
+
+const CPPC_PROBE: usize = 0;
+const CPPC_READ: usize = 1;
+#[cfg(target_pointer_width = "32")]
+const CPPC_READ_HI: usize = 2;
+const CPPC_WRITE: usize = 3;
+
+/// CPPC register
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Register {
+    HighestPerformance = 0x0,
+    NominalPerformance = 0x1,
+    LowestNonlinearPerformance = 0x2,
+    LowestPerformance = 0x3,
+    GuaranteedPerformance = 0x4,
+    DesiredPerformance = 0x5,
+    MinimumPerformance = 0x6,
+    MaximumPerformance = 0x7,
+    PerformanceReductionTolerance = 0x8,
+    TimeWindow = 0x9,
+    CounterWraparoundTime = 0xA,
+    ReferencePerformanceCounter = 0xB,
+    DeliveredPerformanceCounter = 0xC,
+    PerformanceLimited = 0xD,
+    CppcEnable = 0xE,
+    AutonomousSelectionEnable = 0xF,
+    AutonomousActivityWindow = 0x10,
+    EnergyPerformancePreference = 0x11,
+    ReferencePerformance = 0x12,
+    LowestFrequency = 0x13,
+    NominalFrequency = 0x14,
+    TransitionLatency = 0x8000_0000,
+}
+
+/// Returns the width in bits of `register`, or `None` if it is not implemented
+#[inline]
+pub fn probe(register: Register) -> Result<Option<usize>, Error> {
+    unsafe { ecall(EID, CPPC_PROBE, [register as usize, 0, 0, 0, 0, 0]) }
+        .into_result()
+        .map(|width| match width {
+            0 => None,
+            width => Some(width),
+        })
+}
+
+/// Reads `register`
+#[inline]
+pub fn read(register: Register) -> Result<u64, Error> {
+    let lo = unsafe { ecall(EID, CPPC_READ, [register as usize, 0, 0, 0, 0, 0]) }.into_result()?;
+    match () {
+        #[cfg(target_pointer_width = "32")]
+        () => {
+            let hi = unsafe { ecall(EID, CPPC_READ_HI, [register as usize, 0, 0, 0, 0, 0]) }
+                .into_result()?;
+            Ok((hi as u64) << 32 | lo as u64)
+        }
+        #[cfg(not(target_pointer_width = "32"))]
+        () => Ok(lo as u64),
+    }
+}
+
+/// Writes `value` to `register`
+///
+/// # Safety
+///
+/// Changing the performance level of the hart affects the timing of everything running on it.
+#[inline]
+pub unsafe fn write(register: Register, value: u64) -> Result<(), Error> {
+    let (value_lo, value_hi) = split_u64(value);
+    ecall(
+        EID,
+        CPPC_WRITE,
+        [register as usize, value_lo, value_hi, 0, 0, 0],
+    )
+    .into_result()
+    .map(|_| ())
+}
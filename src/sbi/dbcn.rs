@@ -0,0 +1,170 @@
+//! Debug Console extension (EID #0x4442434E "DBCN")
+//!
+//! Provides a byte stream to the console of the SEE, so that S-mode software can print messages
+//! before any UART driver is available. `Console` falls back to the legacy `console_putchar` call
+//! if the SBI implementation does not provide this extension.
+//!
+//! The buffers handed to the SEE are passed by their address, which is assumed to be the physical
+//! address. This holds when the MMU is off or the buffer is identity mapped.
+
+use super::{base, ecall, legacy, Error};
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Extension ID
+pub const EID: usize = 0x4442434E;
+
+pub(crate) const CONSOLE_WRITE: usize = 0;
+pub(crate) const CONSOLE_READ: usize = 1;
+pub(crate) const CONSOLE_WRITE_BYTE: usize = 2;
+
+/// Writes `bytes` to the debug console and returns the number of bytes written
+///
+/// The call does not block; it may write fewer bytes than requested.
+#[inline]
+pub fn console_write(bytes: &[u8]) -> Result<usize, Error> {
+    unsafe {
+        ecall(
+            EID,
+            CONSOLE_WRITE,
+            [bytes.len(), bytes.as_ptr() as usize, 0, 0, 0, 0],
+        )
+    }
+    .into_result()
+}
+
+/// Reads bytes from the debug console into `buf` and returns the number of bytes read
+///
+/// The call does not block; it returns `Ok(0)` if there is nothing to read.
+///
+/// # Safety
+///
+/// The SEE writes to the physical address of `buf`. The caller must ensure that this is the
+/// address `buf` is mapped at.
+#[inline]
+pub unsafe fn console_read(buf: &mut [u8]) -> Result<usize, Error> {
+    ecall(
+        EID,
+        CONSOLE_READ,
+        [buf.len(), buf.as_mut_ptr() as usize, 0, 0, 0, 0],
+    )
+    .into_result()
+}
+
+/// Writes a single byte to the debug console
+///
+/// The call blocks until the byte is written.
+#[inline]
+pub fn console_write_byte(byte: u8) -> Result<(), Error> {
+    unsafe { ecall(EID, CONSOLE_WRITE_BYTE, [byte as usize, 0, 0, 0, 0, 0]) }
+        .into_result()
+        .map(|_| ())
+}
+
+const BACKEND_UNKNOWN: u8 = 0;
+const BACKEND_DBCN: u8 = 1;
+const BACKEND_LEGACY: u8 = 2;
+
+static BACKEND: AtomicU8 = AtomicU8::new(BACKEND_UNKNOWN);
+
+/// Probes for the extension once and caches the result
+#[inline]
+fn has_dbcn() -> bool {
+    match BACKEND.load(Ordering::Relaxed) {
+        BACKEND_DBCN => true,
+        BACKEND_LEGACY => false,
+        _ => {
+            let backend = match base::probe_extension(EID) {
+                true => BACKEND_DBCN,
+                false => BACKEND_LEGACY,
+            };
+            BACKEND.store(backend, Ordering::Relaxed);
+            backend == BACKEND_DBCN
+        }
+    }
+}
+
+/// SBI debug console
+///
+/// Uses the Debug Console extension when available, or the legacy `console_putchar` call
+/// otherwise.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Console;
+
+impl Console {
+    /// Writes all of `bytes` to the console
+    pub fn write_bytes(&mut self, mut bytes: &[u8]) -> Result<(), Error> {
+        if !has_dbcn() {
+            for &byte in bytes {
+                legacy::console_putchar(byte);
+            }
+            return Ok(());
+        }
+        while !bytes.is_empty() {
+            let written = console_write(bytes)?;
+            bytes = &bytes[written..];
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Write for Console {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+
+    crate::interrupt::free(|_| {
+        Console.write_fmt(args).ok();
+    });
+}
+
+/// `log` backend printing to the SBI debug console
+///
+/// Records are formatted as `[LEVEL target] message`. Each record is written inside an
+/// `interrupt::free` critical section, so messages logged from interrupt handlers do not
+/// interleave with the ones they interrupt.
+#[cfg(feature = "log")]
+pub struct Logger;
+
+#[cfg(feature = "log")]
+static LOGGER: Logger = Logger;
+
+#[cfg(feature = "log")]
+impl log::Log for Logger {
+    #[inline]
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            _print(format_args!(
+                "[{:<5} {}] {}\n",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+    }
+
+    #[inline]
+    fn flush(&self) {}
+}
+
+/// Installs `Logger` as the global logger with the given maximum level
+///
+/// # Safety
+///
+/// This function must not be called concurrently with any other `log` initialization function.
+#[cfg(feature = "log")]
+pub unsafe fn init_logger(level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+    log::set_logger_racy(&LOGGER)?;
+    log::set_max_level_racy(level);
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! Legacy extensions (EIDs #0x00 - #0x0F)
+//!
+//! These calls are deprecated in favour of the newer extensions but remain the only option on
+//! older SBI implementations. Unlike the other extensions, they return a single value in `a0`.
+
+use super::ecall;
+
+/// Extension ID of `console_putchar`
+pub const CONSOLE_PUTCHAR: usize = 0x01;
+/// Extension ID of `console_getchar`
+pub const CONSOLE_GETCHAR: usize = 0x02;
+
+/// Writes `byte` to the debug console
+///
+/// Blocks if there are pending characters to be transmitted.
+#[inline]
+pub fn console_putchar(byte: u8) {
+    unsafe { ecall(CONSOLE_PUTCHAR, 0, [byte as usize, 0, 0, 0, 0, 0]) };
+}
+
+/// Reads a byte from the debug console, or returns `None` if there is nothing to read
+#[inline]
+pub fn console_getchar() -> Option<u8> {
+    let ret = unsafe { ecall(CONSOLE_GETCHAR, 0, [0; 6]) };
+    match ret.error {
+        byte @ 0..=0xff => Some(byte as u8),
+        _ => None,
+    }
+}
@@ -0,0 +1,241 @@
+//! Supervisor Binary Interface (SBI)
+//!
+//! Bindings for the calls an S-mode payload makes into the SEE (usually M-mode firmware) through
+//! the `ecall` instruction.
+//!
+//! Ref: [RISC-V Supervisor Binary Interface Specification](https://github.com/riscv-non-isa/riscv-sbi-doc/releases)
+
+pub mod base;
+pub mod cppc;
+pub mod dbcn;
+pub mod legacy;
+pub mod pmu;
+pub mod rfence;
+pub mod server;
+pub mod srst;
+pub mod susp;
+pub mod time;
+
+use bit_field::BitField;
+
+/// Standard SBI error codes
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    Failed,
+    NotSupported,
+    InvalidParam,
+    Denied,
+    InvalidAddress,
+    AlreadyAvailable,
+    AlreadyStarted,
+    AlreadyStopped,
+    NoShmem,
+    InvalidState,
+    BadRange,
+    Timeout,
+    Io,
+    Unknown(isize),
+}
+
+impl Error {
+    /// Converts a non-zero SBI error code into an `Error`
+    #[inline]
+    pub fn from_code(code: isize) -> Self {
+        match code {
+            -1 => Error::Failed,
+            -2 => Error::NotSupported,
+            -3 => Error::InvalidParam,
+            -4 => Error::Denied,
+            -5 => Error::InvalidAddress,
+            -6 => Error::AlreadyAvailable,
+            -7 => Error::AlreadyStarted,
+            -8 => Error::AlreadyStopped,
+            -9 => Error::NoShmem,
+            -10 => Error::InvalidState,
+            -11 => Error::BadRange,
+            -12 => Error::Timeout,
+            -13 => Error::Io,
+            code => Error::Unknown(code),
+        }
+    }
+
+    /// Returns the SBI error code
+    #[inline]
+    pub fn code(&self) -> isize {
+        match *self {
+            Error::Failed => -1,
+            Error::NotSupported => -2,
+            Error::InvalidParam => -3,
+            Error::Denied => -4,
+            Error::InvalidAddress => -5,
+            Error::AlreadyAvailable => -6,
+            Error::AlreadyStarted => -7,
+            Error::AlreadyStopped => -8,
+            Error::NoShmem => -9,
+            Error::InvalidState => -10,
+            Error::BadRange => -11,
+            Error::Timeout => -12,
+            Error::Io => -13,
+            Error::Unknown(code) => code,
+        }
+    }
+}
+
+/// Value pair returned by every SBI call in `a0` and `a1`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SbiRet {
+    /// Error code (`a0`), zero on success
+    pub error: isize,
+    /// Return value (`a1`)
+    pub value: usize,
+}
+
+impl SbiRet {
+    /// Successful return carrying `value`
+    #[inline]
+    pub fn success(value: usize) -> Self {
+        SbiRet { error: 0, value }
+    }
+
+    /// Failed return carrying `error`
+    #[inline]
+    pub fn error(error: Error) -> Self {
+        SbiRet {
+            error: error.code(),
+            value: 0,
+        }
+    }
+
+    /// Converts the pair into a `Result`
+    #[inline]
+    pub fn into_result(self) -> Result<usize, Error> {
+        match self.error {
+            0 => Ok(self.value),
+            code => Err(Error::from_code(code)),
+        }
+    }
+}
+
+impl From<Result<usize, Error>> for SbiRet {
+    #[inline]
+    fn from(result: Result<usize, Error>) -> Self {
+        match result {
+            Ok(value) => SbiRet::success(value),
+            Err(error) => SbiRet::error(error),
+        }
+    }
+}
+
+/// Set of harts passed to the IPI and remote fence calls
+///
+/// Bit `n` of `mask` selects the hart `base + n`. A `base` of `usize::MAX` selects all harts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HartMask {
+    mask: usize,
+    base: usize,
+}
+
+impl HartMask {
+    /// Constructs the set from the `hart_mask` and `hart_mask_base` arguments
+    #[inline]
+    pub fn from_mask_base(mask: usize, base: usize) -> Self {
+        HartMask { mask, base }
+    }
+
+    /// Set of all the harts
+    #[inline]
+    pub fn all() -> Self {
+        HartMask {
+            mask: 0,
+            base: usize::MAX,
+        }
+    }
+
+    /// `hart_mask` argument
+    #[inline]
+    pub fn mask(&self) -> usize {
+        self.mask
+    }
+
+    /// `hart_mask_base` argument
+    #[inline]
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns `true` if `hartid` is in the set
+    #[inline]
+    pub fn contains(&self, hartid: usize) -> bool {
+        if self.base == usize::MAX {
+            return true;
+        }
+        match hartid.checked_sub(self.base) {
+            Some(n) if n < usize::BITS as usize => self.mask.get_bit(n),
+            _ => false,
+        }
+    }
+}
+
+/// Performs an SBI call
+///
+/// `eid` is placed in `a7`, `fid` in `a6` and `args` in `a0`-`a5`.
+///
+/// # Safety
+///
+/// The effects of the call depend on the extension and function being invoked. The caller must
+/// uphold whatever the SBI specification requires for that function (e.g. valid physical
+/// addresses for shared memory).
+#[inline]
+#[allow(unused_variables)]
+pub unsafe fn ecall(eid: usize, fid: usize, args: [usize; 6]) -> SbiRet {
+    match () {
+        #[cfg(riscv)]
+        () => {
+            let error: isize;
+            let value: usize;
+            core::arch::asm!(
+                "ecall",
+                inlateout("a0") args[0] => error,
+                inlateout("a1") args[1] => value,
+                in("a2") args[2],
+                in("a3") args[3],
+                in("a4") args[4],
+                in("a5") args[5],
+                in("a6") fid,
+                in("a7") eid,
+                options(nostack),
+            );
+            SbiRet { error, value }
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
+
+/// Splits a 64-bit argument into the `(low, high)` register pair used on RV32
+///
+/// On RV64 the whole value goes in the first register and the second one is zero.
+#[inline]
+pub(crate) fn split_u64(value: u64) -> (usize, usize) {
+    match () {
+        #[cfg(target_pointer_width = "32")]
+        () => (value as usize, (value >> 32) as usize),
+        #[cfg(not(target_pointer_width = "32"))]
+        () => (value as usize, 0),
+    }
+}
+
+/// Joins the `(low, high)` register pair of a 64-bit argument
+///
+/// On RV64 the whole value is in the first register and the second one is ignored.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn join_u64(lo: usize, hi: usize) -> u64 {
+    match () {
+        #[cfg(target_pointer_width = "32")]
+        () => (hi as u64) << 32 | lo as u64,
+        #[cfg(not(target_pointer_width = "32"))]
+        () => lo as u64,
+    }
+}
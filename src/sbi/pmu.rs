@@ -0,0 +1,409 @@
+//! Performance Monitoring Unit extension (EID #0x504D55 "PMU")
+//!
+//! Lets S-mode discover the available counters, bind them to events and start or stop them.
+//! Hardware counters are read directly through the `cycle`, `instret` and `hpmcounter<3-31>`
+//! CSRs once they are configured, while firmware counters are read with an SBI call.
+
+use super::{ecall, split_u64, Error};
+use crate::register::{self, cycle, instret};
+use bit_field::BitField;
+use core::ops::BitOr;
+
+/// Extension ID
+pub const EID: usize = 0x504D55;
+
+const NUM_COUNTERS: usize = 0;
+const COUNTER_GET_INFO: usize = 1;
+const COUNTER_CONFIG_MATCHING: usize = 2;
+const COUNTER_START: usize = 3;
+const COUNTER_STOP: usize = 4;
+const COUNTER_FW_READ: usize = 5;
+#[cfg(target_pointer_width = "32")]
+const COUNTER_FW_READ_HI: usize = 6;
+
+/// Counter information returned by `counter_get_info`
+#[derive(Clone, Copy, Debug)]
+pub struct CounterInfo {
+    bits: usize,
+}
+
+/// Counter implementation
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CounterType {
+    /// Backed by a hardware CSR
+    Hardware = 0,
+    /// Implemented by the SBI firmware
+    Firmware = 1,
+}
+
+impl CounterInfo {
+    /// Returns the contents of the info as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// CSR number of a hardware counter
+    #[inline]
+    pub fn csr(&self) -> usize {
+        self.bits.get_bits(0..12)
+    }
+
+    /// Width of the counter in bits
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.bits.get_bits(12..18) + 1
+    }
+
+    /// Counter implementation
+    #[inline]
+    pub fn counter_type(&self) -> CounterType {
+        match self.bits.get_bit(usize::BITS as usize - 1) {
+            false => CounterType::Hardware,
+            true => CounterType::Firmware,
+        }
+    }
+}
+
+/// Generalized hardware events (event type #0)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HardwareEvent {
+    CpuCycles = 1,
+    Instructions = 2,
+    CacheReferences = 3,
+    CacheMisses = 4,
+    BranchInstructions = 5,
+    BranchMisses = 6,
+    BusCycles = 7,
+    StalledCyclesFrontend = 8,
+    StalledCyclesBackend = 9,
+    RefCpuCycles = 10,
+}
+
+/// Cache of a hardware cache event (event type #1)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CacheId {
+    L1D = 0,
+    L1I = 1,
+    LL = 2,
+    DTLB = 3,
+    ITLB = 4,
+    BPU = 5,
+    Node = 6,
+}
+
+/// Operation of a hardware cache event
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CacheOp {
+    Read = 0,
+    Write = 1,
+    Prefetch = 2,
+}
+
+/// Result of a hardware cache event
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CacheResult {
+    Access = 0,
+    Miss = 1,
+}
+
+/// Firmware events (event type #15)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FirmwareEvent {
+    MisalignedLoad = 0,
+    MisalignedStore = 1,
+    AccessLoad = 2,
+    AccessStore = 3,
+    IllegalInsn = 4,
+    SetTimer = 5,
+    IpiSent = 6,
+    IpiReceived = 7,
+    FenceISent = 8,
+    FenceIReceived = 9,
+    SfenceVmaSent = 10,
+    SfenceVmaReceived = 11,
+    SfenceVmaAsidSent = 12,
+    SfenceVmaAsidReceived = 13,
+    HfenceGvmaSent = 14,
+    HfenceGvmaReceived = 15,
+    HfenceGvmaVmidSent = 16,
+    HfenceGvmaVmidReceived = 17,
+    HfenceVvmaSent = 18,
+    HfenceVvmaReceived = 19,
+    HfenceVvmaAsidSent = 20,
+    HfenceVvmaAsidReceived = 21,
+}
+
+/// Event to be monitored by a counter
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// Generalized hardware event
+    Hardware(HardwareEvent),
+    /// Hardware cache event
+    Cache {
+        cache: CacheId,
+        op: CacheOp,
+        result: CacheResult,
+    },
+    /// Platform specific raw hardware event, as programmed into `mhpmevent<3-31>`
+    Raw(u64),
+    /// Firmware event
+    Firmware(FirmwareEvent),
+}
+
+impl Event {
+    /// Returns the `event_idx` argument: event type in bits 16-19 and event code in bits 0-15
+    #[inline]
+    pub fn index(&self) -> usize {
+        match *self {
+            Event::Hardware(event) => event as usize,
+            Event::Cache { cache, op, result } => {
+                1 << 16 | (cache as usize) << 3 | (op as usize) << 1 | result as usize
+            }
+            Event::Raw(_) => 2 << 16,
+            Event::Firmware(event) => 0xf << 16 | event as usize,
+        }
+    }
+
+    /// Returns the `event_data` argument
+    #[inline]
+    pub fn data(&self) -> u64 {
+        match *self {
+            Event::Raw(data) => data,
+            _ => 0,
+        }
+    }
+}
+
+/// Flags of `counter_config_matching`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConfigFlags(usize);
+
+impl ConfigFlags {
+    /// No flags set
+    pub const NONE: Self = ConfigFlags(0);
+    /// Skip the counter matching and configure the counter given by `counter_idx_base`
+    pub const SKIP_MATCH: Self = ConfigFlags(1 << 0);
+    /// Clear the counter value
+    pub const CLEAR_VALUE: Self = ConfigFlags(1 << 1);
+    /// Start the counter after configuring it
+    pub const AUTO_START: Self = ConfigFlags(1 << 2);
+    /// Do not count events in VU-mode
+    pub const SET_VUINH: Self = ConfigFlags(1 << 3);
+    /// Do not count events in VS-mode
+    pub const SET_VSINH: Self = ConfigFlags(1 << 4);
+    /// Do not count events in U-mode
+    pub const SET_UINH: Self = ConfigFlags(1 << 5);
+    /// Do not count events in S-mode
+    pub const SET_SINH: Self = ConfigFlags(1 << 6);
+    /// Do not count events in M-mode
+    pub const SET_MINH: Self = ConfigFlags(1 << 7);
+
+    /// Returns the flags as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.0
+    }
+}
+
+impl BitOr for ConfigFlags {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        ConfigFlags(self.0 | rhs.0)
+    }
+}
+
+/// Flags of `counter_start`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StartFlags(usize);
+
+impl StartFlags {
+    /// No flags set
+    pub const NONE: Self = StartFlags(0);
+    /// Set the counters to `initial_value` before starting them
+    pub const SET_INIT_VALUE: Self = StartFlags(1 << 0);
+
+    /// Returns the flags as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.0
+    }
+}
+
+/// Flags of `counter_stop`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StopFlags(usize);
+
+impl StopFlags {
+    /// No flags set
+    pub const NONE: Self = StopFlags(0);
+    /// Reset the counter to event mapping after stopping the counters
+    pub const RESET: Self = StopFlags(1 << 0);
+
+    /// Returns the flags as raw bits
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.0
+    }
+}
+
+/// Returns the number of counters, both hardware and firmware
+#[inline]
+pub fn num_counters() -> Result<usize, Error> {
+    unsafe { ecall(EID, NUM_COUNTERS, [0; 6]) }.into_result()
+}
+
+/// Returns the details of the counter `counter_idx`
+#[inline]
+pub fn counter_get_info(counter_idx: usize) -> Result<CounterInfo, Error> {
+    unsafe { ecall(EID, COUNTER_GET_INFO, [counter_idx, 0, 0, 0, 0, 0]) }
+        .into_result()
+        .map(|bits| CounterInfo { bits })
+}
+
+/// Finds and configures a counter among `counter_idx_base + n` (`n` set in `counter_idx_mask`)
+/// that can monitor `event`, and returns its index
+///
+/// # Safety
+///
+/// Reconfiguring a counter changes what other users of that counter observe.
+#[inline]
+pub unsafe fn counter_config_matching(
+    counter_idx_base: usize,
+    counter_idx_mask: usize,
+    config_flags: ConfigFlags,
+    event: Event,
+) -> Result<usize, Error> {
+    let (data_lo, data_hi) = split_u64(event.data());
+    ecall(
+        EID,
+        COUNTER_CONFIG_MATCHING,
+        [
+            counter_idx_base,
+            counter_idx_mask,
+            config_flags.bits(),
+            event.index(),
+            data_lo,
+            data_hi,
+        ],
+    )
+    .into_result()
+}
+
+/// Starts the counters `counter_idx_base + n` (`n` set in `counter_idx_mask`)
+///
+/// # Safety
+///
+/// Starting a counter changes what other users of that counter observe.
+#[inline]
+pub unsafe fn counter_start(
+    counter_idx_base: usize,
+    counter_idx_mask: usize,
+    start_flags: StartFlags,
+    initial_value: u64,
+) -> Result<(), Error> {
+    let (value_lo, value_hi) = split_u64(initial_value);
+    ecall(
+        EID,
+        COUNTER_START,
+        [
+            counter_idx_base,
+            counter_idx_mask,
+            start_flags.bits(),
+            value_lo,
+            value_hi,
+            0,
+        ],
+    )
+    .into_result()
+    .map(|_| ())
+}
+
+/// Stops the counters `counter_idx_base + n` (`n` set in `counter_idx_mask`)
+///
+/// # Safety
+///
+/// Stopping a counter changes what other users of that counter observe.
+#[inline]
+pub unsafe fn counter_stop(
+    counter_idx_base: usize,
+    counter_idx_mask: usize,
+    stop_flags: StopFlags,
+) -> Result<(), Error> {
+    ecall(
+        EID,
+        COUNTER_STOP,
+        [
+            counter_idx_base,
+            counter_idx_mask,
+            stop_flags.bits(),
+            0,
+            0,
+            0,
+        ],
+    )
+    .into_result()
+    .map(|_| ())
+}
+
+/// Reads the current value of the firmware counter `counter_idx`
+#[inline]
+pub fn counter_fw_read(counter_idx: usize) -> Result<u64, Error> {
+    let lo = unsafe { ecall(EID, COUNTER_FW_READ, [counter_idx, 0, 0, 0, 0, 0]) }.into_result()?;
+    match () {
+        #[cfg(target_pointer_width = "32")]
+        () => {
+            let hi = unsafe { ecall(EID, COUNTER_FW_READ_HI, [counter_idx, 0, 0, 0, 0, 0]) }
+                .into_result()?;
+            Ok((hi as u64) << 32 | lo as u64)
+        }
+        #[cfg(not(target_pointer_width = "32"))]
+        () => Ok(lo as u64),
+    }
+}
+
+/// Reads the current value of the counter `counter_idx`
+///
+/// Hardware counters are read through their `cycle`, `instret` or `hpmcounter<3-31>` CSR, which
+/// must be made accessible from S-mode through `mcounteren`. Firmware counters are read with
+/// `counter_fw_read`.
+#[inline]
+pub fn counter_read(counter_idx: usize) -> Result<u64, Error> {
+    let info = counter_get_info(counter_idx)?;
+    let value = match info.counter_type() {
+        CounterType::Firmware => return counter_fw_read(counter_idx),
+        CounterType::Hardware => read_hardware_counter(info.csr())?,
+    };
+    match info.width() {
+        64 => Ok(value),
+        width => Ok(value & ((1 << width) - 1)),
+    }
+}
+
+#[inline]
+fn read_hardware_counter(csr: usize) -> Result<u64, Error> {
+    macro_rules! hpm {
+        ($($number:literal => $csr:ident),* $(,)?) => {
+            match csr {
+                0xC00 => Ok(cycle::read64()),
+                0xC02 => Ok(instret::read64()),
+                $($number => Ok(register::$csr::read64()),)*
+                _ => Err(Error::NotSupported),
+            }
+        };
+    }
+    hpm!(
+        0xC03 => hpmcounter3, 0xC04 => hpmcounter4, 0xC05 => hpmcounter5,
+        0xC06 => hpmcounter6, 0xC07 => hpmcounter7, 0xC08 => hpmcounter8,
+        0xC09 => hpmcounter9, 0xC0A => hpmcounter10, 0xC0B => hpmcounter11,
+        0xC0C => hpmcounter12, 0xC0D => hpmcounter13, 0xC0E => hpmcounter14,
+        0xC0F => hpmcounter15, 0xC10 => hpmcounter16, 0xC11 => hpmcounter17,
+        0xC12 => hpmcounter18, 0xC13 => hpmcounter19, 0xC14 => hpmcounter20,
+        0xC15 => hpmcounter21, 0xC16 => hpmcounter22, 0xC17 => hpmcounter23,
+        0xC18 => hpmcounter24, 0xC19 => hpmcounter25, 0xC1A => hpmcounter26,
+        0xC1B => hpmcounter27, 0xC1C => hpmcounter28, 0xC1D => hpmcounter29,
+        0xC1E => hpmcounter30, 0xC1F => hpmcounter31,
+    )
+}
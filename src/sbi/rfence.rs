@@ -0,0 +1,73 @@
+//! RFENCE extension (EID #0x52464E43 "RFNC")
+//!
+//! Asks the SBI implementation to execute fences on other harts, on behalf of the caller.
+
+use super::{ecall, Error, HartMask};
+
+/// Extension ID
+pub const EID: usize = 0x52464E43;
+
+pub(crate) const REMOTE_FENCE_I: usize = 0;
+pub(crate) const REMOTE_SFENCE_VMA: usize = 1;
+pub(crate) const REMOTE_SFENCE_VMA_ASID: usize = 2;
+
+/// Executes `FENCE.I` on the harts of `hart_mask`
+///
+/// Returns once the instruction caches of the harts are synchronized with the stores done before
+/// the call.
+#[inline]
+pub fn remote_fence_i(hart_mask: HartMask) -> Result<(), Error> {
+    unsafe {
+        ecall(
+            EID,
+            REMOTE_FENCE_I,
+            [hart_mask.mask(), hart_mask.base(), 0, 0, 0, 0],
+        )
+    }
+    .into_result()
+    .map(|_| ())
+}
+
+/// Executes `SFENCE.VMA` on the harts of `hart_mask`, for the virtual addresses from `start_addr`
+/// to `start_addr + size`
+///
+/// A `size` of `usize::MAX` (or a `start_addr` and `size` of 0) flushes all the addresses.
+#[inline]
+pub fn remote_sfence_vma(hart_mask: HartMask, start_addr: usize, size: usize) -> Result<(), Error> {
+    unsafe {
+        ecall(
+            EID,
+            REMOTE_SFENCE_VMA,
+            [hart_mask.mask(), hart_mask.base(), start_addr, size, 0, 0],
+        )
+    }
+    .into_result()
+    .map(|_| ())
+}
+
+/// Executes `SFENCE.VMA` on the harts of `hart_mask`, for the virtual addresses from `start_addr`
+/// to `start_addr + size` of the address space `asid`
+#[inline]
+pub fn remote_sfence_vma_asid(
+    hart_mask: HartMask,
+    start_addr: usize,
+    size: usize,
+    asid: usize,
+) -> Result<(), Error> {
+    unsafe {
+        ecall(
+            EID,
+            REMOTE_SFENCE_VMA_ASID,
+            [
+                hart_mask.mask(),
+                hart_mask.base(),
+                start_addr,
+                size,
+                asid,
+                0,
+            ],
+        )
+    }
+    .into_result()
+    .map(|_| ())
+}
@@ -0,0 +1,524 @@
+//! SBI implementation framework (M-mode side)
+//!
+//! Decodes the `ecall` traps taken from S-mode into typed `Request`s and dispatches them to the
+//! extensions provided by an `Sbi` implementation. Each extension is a separate trait
+//! (`SbiTimer`, `SbiIpi`, ...) so that firmware only implements what the platform supports; the
+//! base extension and `probe_extension` are handled here.
+//!
+//! The trap handler is expected to save the general purpose registers of the trapped context in
+//! an array indexed by register number (`regs[10]` is `a0`) and call `handle_ecall` when `mcause`
+//! reports `Exception::SupervisorEnvCall`:
+//!
+//! ```no_run
+//! use riscv::register::mip;
+//! use riscv::sbi::server::{self, Sbi, SbiTimer};
+//!
+//! struct Timer;
+//!
+//! impl SbiTimer for Timer {
+//!     fn set_timer(&mut self, stime_value: u64) {
+//!         // program the platform `mtimecmp` with `stime_value` here
+//!         unsafe { mip::clear_stimer() };
+//!     }
+//! }
+//!
+//! struct Firmware {
+//!     timer: Timer,
+//! }
+//!
+//! impl Sbi for Firmware {
+//!     fn impl_id(&self) -> usize {
+//!         0xffff
+//!     }
+//!
+//!     fn impl_version(&self) -> usize {
+//!         1
+//!     }
+//!
+//!     fn timer(&mut self) -> Option<&mut dyn SbiTimer> {
+//!         Some(&mut self.timer)
+//!     }
+//! }
+//!
+//! fn on_supervisor_ecall(firmware: &mut Firmware, regs: &mut [usize; 32]) {
+//!     server::handle_ecall(firmware, regs);
+//! }
+//! ```
+
+use super::srst::{ResetReason, ResetType};
+use super::{base, dbcn, join_u64, rfence, srst, time, Error, HartMask, SbiRet};
+use crate::register::{marchid, mepc, mimpid, mvendorid};
+
+/// Version of the SBI specification reported by `get_spec_version` (v2.0)
+pub const SPEC_VERSION: usize = 2 << 24;
+
+const IPI_EID: usize = 0x735049;
+const HSM_EID: usize = 0x48534D;
+
+const LEGACY_SET_TIMER: usize = 0x00;
+const LEGACY_CONSOLE_PUTCHAR: usize = 0x01;
+const LEGACY_CONSOLE_GETCHAR: usize = 0x02;
+const LEGACY_SHUTDOWN: usize = 0x08;
+
+const A0: usize = 10;
+const A1: usize = 11;
+
+/// Timer extension (EID #0x54494D45 "TIME")
+pub trait SbiTimer {
+    /// Programs the supervisor timer to fire at `stime_value` and clears any pending supervisor
+    /// timer interrupt (`mip.STIP`)
+    fn set_timer(&mut self, stime_value: u64);
+}
+
+/// IPI extension (EID #0x735049 "sPI")
+pub trait SbiIpi {
+    /// Sends a supervisor software interrupt to the harts in `hart_mask`
+    fn send_ipi(&mut self, hart_mask: HartMask) -> SbiRet;
+}
+
+/// RFENCE extension (EID #0x52464E43 "RFNC")
+pub trait SbiRfence {
+    /// Executes `FENCE.I` on the harts in `hart_mask`
+    fn remote_fence_i(&mut self, hart_mask: HartMask) -> SbiRet;
+
+    /// Executes `SFENCE.VMA` for the given range on the harts in `hart_mask`
+    fn remote_sfence_vma(&mut self, hart_mask: HartMask, start_addr: usize, size: usize) -> SbiRet;
+
+    /// Executes `SFENCE.VMA` for the given range and ASID on the harts in `hart_mask`
+    fn remote_sfence_vma_asid(
+        &mut self,
+        hart_mask: HartMask,
+        start_addr: usize,
+        size: usize,
+        asid: usize,
+    ) -> SbiRet;
+}
+
+/// Hart State Management extension (EID #0x48534D "HSM")
+pub trait SbiHsm {
+    /// Starts `hartid` in S-mode at `start_addr` with `opaque` in `a1`
+    fn hart_start(&mut self, hartid: usize, start_addr: usize, opaque: usize) -> SbiRet;
+
+    /// Stops the calling hart; only returns on failure
+    fn hart_stop(&mut self) -> SbiRet;
+
+    /// Returns the status of `hartid`
+    fn hart_get_status(&mut self, hartid: usize) -> SbiRet;
+
+    /// Suspends the calling hart
+    fn hart_suspend(&mut self, suspend_type: u32, resume_addr: usize, opaque: usize) -> SbiRet;
+}
+
+/// System Reset extension (EID #0x53525354 "SRST")
+pub trait SbiReset {
+    /// Resets the system; only returns on failure
+    fn system_reset(&mut self, reset_type: ResetType, reset_reason: ResetReason) -> SbiRet;
+}
+
+/// Debug Console extension (EID #0x4442434E "DBCN")
+///
+/// The buffers are given by their physical address in S-mode.
+pub trait SbiConsole {
+    /// Writes `num_bytes` bytes from `base_addr` to the console
+    fn console_write(&mut self, num_bytes: usize, base_addr: u64) -> SbiRet;
+
+    /// Reads up to `num_bytes` bytes from the console into `base_addr`
+    fn console_read(&mut self, num_bytes: usize, base_addr: u64) -> SbiRet;
+
+    /// Writes `byte` to the console
+    fn console_write_byte(&mut self, byte: u8) -> SbiRet;
+
+    /// Reads a byte from the console without blocking, for the legacy `console_getchar` call
+    fn console_getchar(&mut self) -> Option<u8> {
+        None
+    }
+}
+
+/// SBI implementation
+///
+/// Each extension is reported as available by `probe_extension` when its accessor returns `Some`.
+pub trait Sbi {
+    /// SBI implementation ID
+    fn impl_id(&self) -> usize;
+
+    /// SBI implementation version
+    fn impl_version(&self) -> usize;
+
+    /// Timer extension
+    fn timer(&mut self) -> Option<&mut dyn SbiTimer> {
+        None
+    }
+
+    /// IPI extension
+    fn ipi(&mut self) -> Option<&mut dyn SbiIpi> {
+        None
+    }
+
+    /// RFENCE extension
+    fn rfence(&mut self) -> Option<&mut dyn SbiRfence> {
+        None
+    }
+
+    /// Hart State Management extension
+    fn hsm(&mut self) -> Option<&mut dyn SbiHsm> {
+        None
+    }
+
+    /// System Reset extension
+    fn reset(&mut self) -> Option<&mut dyn SbiReset> {
+        None
+    }
+
+    /// Debug Console extension
+    fn console(&mut self) -> Option<&mut dyn SbiConsole> {
+        None
+    }
+}
+
+/// SBI call decoded from the registers of the trapped context
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Request {
+    GetSpecVersion,
+    GetImplId,
+    GetImplVersion,
+    ProbeExtension(usize),
+    GetMvendorid,
+    GetMarchid,
+    GetMimpid,
+    SetTimer(u64),
+    SendIpi(HartMask),
+    RemoteFenceI(HartMask),
+    RemoteSfenceVma {
+        hart_mask: HartMask,
+        start_addr: usize,
+        size: usize,
+    },
+    RemoteSfenceVmaAsid {
+        hart_mask: HartMask,
+        start_addr: usize,
+        size: usize,
+        asid: usize,
+    },
+    HartStart {
+        hartid: usize,
+        start_addr: usize,
+        opaque: usize,
+    },
+    HartStop,
+    HartGetStatus(usize),
+    HartSuspend {
+        suspend_type: u32,
+        resume_addr: usize,
+        opaque: usize,
+    },
+    SystemReset {
+        reset_type: ResetType,
+        reset_reason: ResetReason,
+    },
+    ConsoleWrite {
+        num_bytes: usize,
+        base_addr: u64,
+    },
+    ConsoleRead {
+        num_bytes: usize,
+        base_addr: u64,
+    },
+    ConsoleWriteByte(u8),
+    LegacySetTimer(u64),
+    LegacyConsolePutchar(u8),
+    LegacyConsoleGetchar,
+    LegacyShutdown,
+    /// Call with invalid arguments
+    InvalidParam,
+    /// Unknown extension or function
+    NotSupported {
+        eid: usize,
+        fid: usize,
+    },
+}
+
+impl Request {
+    /// Decodes the call in `a0`-`a7` of the saved registers
+    pub fn decode(regs: &[usize; 32]) -> Self {
+        let a = |n: usize| regs[A0 + n];
+        let (eid, fid) = (a(7), a(6));
+        match (eid, fid) {
+            (base::EID, base::GET_SPEC_VERSION) => Request::GetSpecVersion,
+            (base::EID, base::GET_IMPL_ID) => Request::GetImplId,
+            (base::EID, base::GET_IMPL_VERSION) => Request::GetImplVersion,
+            (base::EID, base::PROBE_EXTENSION) => Request::ProbeExtension(a(0)),
+            (base::EID, base::GET_MVENDORID) => Request::GetMvendorid,
+            (base::EID, base::GET_MARCHID) => Request::GetMarchid,
+            (base::EID, base::GET_MIMPID) => Request::GetMimpid,
+            (time::EID, time::SET_TIMER) => Request::SetTimer(join_u64(a(0), a(1))),
+            (IPI_EID, 0) => Request::SendIpi(HartMask::from_mask_base(a(0), a(1))),
+            (rfence::EID, rfence::REMOTE_FENCE_I) => {
+                Request::RemoteFenceI(HartMask::from_mask_base(a(0), a(1)))
+            }
+            (rfence::EID, rfence::REMOTE_SFENCE_VMA) => Request::RemoteSfenceVma {
+                hart_mask: HartMask::from_mask_base(a(0), a(1)),
+                start_addr: a(2),
+                size: a(3),
+            },
+            (rfence::EID, rfence::REMOTE_SFENCE_VMA_ASID) => Request::RemoteSfenceVmaAsid {
+                hart_mask: HartMask::from_mask_base(a(0), a(1)),
+                start_addr: a(2),
+                size: a(3),
+                asid: a(4),
+            },
+            (HSM_EID, 0) => Request::HartStart {
+                hartid: a(0),
+                start_addr: a(1),
+                opaque: a(2),
+            },
+            (HSM_EID, 1) => Request::HartStop,
+            (HSM_EID, 2) => Request::HartGetStatus(a(0)),
+            (HSM_EID, 3) => Request::HartSuspend {
+                suspend_type: a(0) as u32,
+                resume_addr: a(1),
+                opaque: a(2),
+            },
+            (srst::EID, srst::SYSTEM_RESET) => match (
+                ResetType::from_code(a(0) as u32),
+                ResetReason::from_code(a(1) as u32),
+            ) {
+                (Some(reset_type), Some(reset_reason)) => Request::SystemReset {
+                    reset_type,
+                    reset_reason,
+                },
+                _ => Request::InvalidParam,
+            },
+            (dbcn::EID, dbcn::CONSOLE_WRITE) => Request::ConsoleWrite {
+                num_bytes: a(0),
+                base_addr: join_u64(a(1), a(2)),
+            },
+            (dbcn::EID, dbcn::CONSOLE_READ) => Request::ConsoleRead {
+                num_bytes: a(0),
+                base_addr: join_u64(a(1), a(2)),
+            },
+            (dbcn::EID, dbcn::CONSOLE_WRITE_BYTE) => Request::ConsoleWriteByte(a(0) as u8),
+            (LEGACY_SET_TIMER, _) => Request::LegacySetTimer(join_u64(a(0), a(1))),
+            (LEGACY_CONSOLE_PUTCHAR, _) => Request::LegacyConsolePutchar(a(0) as u8),
+            (LEGACY_CONSOLE_GETCHAR, _) => Request::LegacyConsoleGetchar,
+            (LEGACY_SHUTDOWN, _) => Request::LegacyShutdown,
+            (eid, fid) => Request::NotSupported { eid, fid },
+        }
+    }
+
+    /// Returns `true` for the legacy calls, which only return a value in `a0`
+    #[inline]
+    pub fn is_legacy(&self) -> bool {
+        matches!(
+            self,
+            Request::LegacySetTimer(_)
+                | Request::LegacyConsolePutchar(_)
+                | Request::LegacyConsoleGetchar
+                | Request::LegacyShutdown
+        )
+    }
+}
+
+/// Returns `true` if `sbi` provides the extension `eid`
+pub fn probe_extension<S: Sbi>(sbi: &mut S, eid: usize) -> bool {
+    match eid {
+        base::EID => true,
+        time::EID | LEGACY_SET_TIMER => sbi.timer().is_some(),
+        IPI_EID => sbi.ipi().is_some(),
+        rfence::EID => sbi.rfence().is_some(),
+        HSM_EID => sbi.hsm().is_some(),
+        srst::EID | LEGACY_SHUTDOWN => sbi.reset().is_some(),
+        dbcn::EID | LEGACY_CONSOLE_PUTCHAR | LEGACY_CONSOLE_GETCHAR => sbi.console().is_some(),
+        _ => false,
+    }
+}
+
+/// Executes `request` on `sbi`
+pub fn dispatch<S: Sbi>(sbi: &mut S, request: Request) -> SbiRet {
+    let not_supported = SbiRet::error(Error::NotSupported);
+    match request {
+        Request::GetSpecVersion => SbiRet::success(SPEC_VERSION),
+        Request::GetImplId => SbiRet::success(sbi.impl_id()),
+        Request::GetImplVersion => SbiRet::success(sbi.impl_version()),
+        Request::ProbeExtension(eid) => SbiRet::success(probe_extension(sbi, eid) as usize),
+        Request::GetMvendorid => SbiRet::success(mvendorid::read().map_or(0, |r| r.bits())),
+        Request::GetMarchid => SbiRet::success(marchid::read().map_or(0, |r| r.bits())),
+        Request::GetMimpid => SbiRet::success(mimpid::read().map_or(0, |r| r.bits())),
+        Request::SetTimer(stime_value) | Request::LegacySetTimer(stime_value) => {
+            match sbi.timer() {
+                Some(timer) => {
+                    timer.set_timer(stime_value);
+                    SbiRet::success(0)
+                }
+                None => not_supported,
+            }
+        }
+        Request::SendIpi(hart_mask) => match sbi.ipi() {
+            Some(ipi) => ipi.send_ipi(hart_mask),
+            None => not_supported,
+        },
+        Request::RemoteFenceI(hart_mask) => match sbi.rfence() {
+            Some(rfence) => rfence.remote_fence_i(hart_mask),
+            None => not_supported,
+        },
+        Request::RemoteSfenceVma {
+            hart_mask,
+            start_addr,
+            size,
+        } => match sbi.rfence() {
+            Some(rfence) => rfence.remote_sfence_vma(hart_mask, start_addr, size),
+            None => not_supported,
+        },
+        Request::RemoteSfenceVmaAsid {
+            hart_mask,
+            start_addr,
+            size,
+            asid,
+        } => match sbi.rfence() {
+            Some(rfence) => rfence.remote_sfence_vma_asid(hart_mask, start_addr, size, asid),
+            None => not_supported,
+        },
+        Request::HartStart {
+            hartid,
+            start_addr,
+            opaque,
+        } => match sbi.hsm() {
+            Some(hsm) => hsm.hart_start(hartid, start_addr, opaque),
+            None => not_supported,
+        },
+        Request::HartStop => match sbi.hsm() {
+            Some(hsm) => hsm.hart_stop(),
+            None => not_supported,
+        },
+        Request::HartGetStatus(hartid) => match sbi.hsm() {
+            Some(hsm) => hsm.hart_get_status(hartid),
+            None => not_supported,
+        },
+        Request::HartSuspend {
+            suspend_type,
+            resume_addr,
+            opaque,
+        } => match sbi.hsm() {
+            Some(hsm) => hsm.hart_suspend(suspend_type, resume_addr, opaque),
+            None => not_supported,
+        },
+        Request::SystemReset {
+            reset_type,
+            reset_reason,
+        } => match sbi.reset() {
+            Some(reset) => reset.system_reset(reset_type, reset_reason),
+            None => not_supported,
+        },
+        Request::LegacyShutdown => match sbi.reset() {
+            Some(reset) => reset.system_reset(ResetType::Shutdown, ResetReason::NoReason),
+            None => not_supported,
+        },
+        Request::ConsoleWrite {
+            num_bytes,
+            base_addr,
+        } => match sbi.console() {
+            Some(console) => console.console_write(num_bytes, base_addr),
+            None => not_supported,
+        },
+        Request::ConsoleRead {
+            num_bytes,
+            base_addr,
+        } => match sbi.console() {
+            Some(console) => console.console_read(num_bytes, base_addr),
+            None => not_supported,
+        },
+        Request::ConsoleWriteByte(byte) | Request::LegacyConsolePutchar(byte) => {
+            match sbi.console() {
+                Some(console) => console.console_write_byte(byte),
+                None => not_supported,
+            }
+        }
+        Request::LegacyConsoleGetchar => match sbi.console().and_then(|c| c.console_getchar()) {
+            Some(byte) => SbiRet::success(byte as usize),
+            None => SbiRet::error(Error::Failed),
+        },
+        Request::InvalidParam => SbiRet::error(Error::InvalidParam),
+        Request::NotSupported { .. } => not_supported,
+    }
+}
+
+/// Handles an `ecall` from S-mode
+///
+/// Decodes the call from the saved registers `regs`, dispatches it to `sbi`, stores the result in
+/// `a0` and `a1` (only `a0` for the legacy calls) and advances `mepc` past the `ecall`.
+pub fn handle_ecall<S: Sbi>(sbi: &mut S, regs: &mut [usize; 32]) {
+    let request = Request::decode(regs);
+    let ret = dispatch(sbi, request);
+    match request {
+        // `console_getchar` returns the byte in `a0`, the other legacy calls an error code
+        Request::LegacyConsoleGetchar => {
+            regs[A0] = match ret.into_result() {
+                Ok(byte) => byte,
+                Err(_) => usize::MAX,
+            }
+        }
+        request if request.is_legacy() => regs[A0] = ret.error as usize,
+        _ => {
+            regs[A0] = ret.error as usize;
+            regs[A1] = ret.value;
+        }
+    }
+    mepc::write(mepc::read().wrapping_add(4));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(eid: usize, fid: usize, args: &[usize]) -> [usize; 32] {
+        let mut regs = [0; 32];
+        regs[A0..A0 + args.len()].copy_from_slice(args);
+        regs[A0 + 6] = fid;
+        regs[A0 + 7] = eid;
+        regs
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(
+            Request::decode(&call(base::EID, 3, &[0x735049])),
+            Request::ProbeExtension(0x735049)
+        );
+        assert_eq!(
+            Request::decode(&call(rfence::EID, 2, &[0b101, 4, 0x1000, 0x2000, 7])),
+            Request::RemoteSfenceVmaAsid {
+                hart_mask: HartMask::from_mask_base(0b101, 4),
+                start_addr: 0x1000,
+                size: 0x2000,
+                asid: 7,
+            }
+        );
+        assert_eq!(
+            Request::decode(&call(srst::EID, 0, &[1, 0xF000_0002])),
+            Request::SystemReset {
+                reset_type: ResetType::ColdReboot,
+                reset_reason: ResetReason::VendorSpecific(2),
+            }
+        );
+        assert_eq!(
+            Request::decode(&call(srst::EID, 0, &[3, 0])),
+            Request::InvalidParam
+        );
+        assert_eq!(
+            Request::decode(&call(0x0A000000, 1, &[])),
+            Request::NotSupported {
+                eid: 0x0A000000,
+                fid: 1
+            }
+        );
+    }
+
+    #[test]
+    fn hart_mask() {
+        let mask = HartMask::from_mask_base(0b101, 4);
+        assert!(mask.contains(4));
+        assert!(!mask.contains(5));
+        assert!(mask.contains(6));
+        assert!(!mask.contains(0));
+        assert!(HartMask::all().contains(1234));
+    }
+}
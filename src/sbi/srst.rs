@@ -0,0 +1,90 @@
+//! System Reset extension (EID #0x53525354 "SRST")
+
+use super::{ecall, Error};
+
+/// Extension ID
+pub const EID: usize = 0x53525354;
+
+pub(crate) const SYSTEM_RESET: usize = 0;
+
+/// Reset type
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResetType {
+    Shutdown = 0,
+    ColdReboot = 1,
+    WarmReboot = 2,
+}
+
+/// Reset reason
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResetReason {
+    NoReason,
+    SystemFailure,
+    /// SBI implementation specific reason (lower 28 bits)
+    SbiSpecific(u32),
+    /// Vendor or platform specific reason (lower 28 bits)
+    VendorSpecific(u32),
+}
+
+impl ResetType {
+    /// Converts a `reset_type` argument, returning `None` for reserved values
+    #[inline]
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(ResetType::Shutdown),
+            1 => Some(ResetType::ColdReboot),
+            2 => Some(ResetType::WarmReboot),
+            _ => None,
+        }
+    }
+}
+
+impl ResetReason {
+    /// Converts a `reset_reason` argument, returning `None` for reserved values
+    #[inline]
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(ResetReason::NoReason),
+            1 => Some(ResetReason::SystemFailure),
+            0xE000_0000..=0xEFFF_FFFF => Some(ResetReason::SbiSpecific(code & 0x0FFF_FFFF)),
+            0xF000_0000..=0xFFFF_FFFF => Some(ResetReason::VendorSpecific(code & 0x0FFF_FFFF)),
+            _ => None,
+        }
+    }
+
+    /// Returns the `reset_reason` argument
+    #[inline]
+    pub fn code(&self) -> u32 {
+        match *self {
+            ResetReason::NoReason => 0,
+            ResetReason::SystemFailure => 1,
+            ResetReason::SbiSpecific(reason) => 0xE000_0000 | (reason & 0x0FFF_FFFF),
+            ResetReason::VendorSpecific(reason) => 0xF000_0000 | (reason & 0x0FFF_FFFF),
+        }
+    }
+}
+
+/// Resets the system
+///
+/// Does not return on success, so the returned value is always the reason of the failure.
+#[inline]
+pub fn system_reset(reset_type: ResetType, reset_reason: ResetReason) -> Error {
+    let ret = unsafe {
+        ecall(
+            EID,
+            SYSTEM_RESET,
+            [
+                reset_type as usize,
+                reset_reason.code() as usize,
+                0,
+                0,
+                0,
+                0,
+            ],
+        )
+    };
+    match ret.into_result() {
+        Ok(_) => Error::Failed,
+        Err(error) => error,
+    }
+}
@@ -0,0 +1,52 @@
+//! System Suspend extension (EID #0x53555350 "SUSP")
+
+use super::{ecall, Error};
+
+/// Extension ID
+pub const EID: usize = 0x53555350;
+
+const SYSTEM_SUSPEND: usize = 0;
+
+/// Sleep type
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SleepType {
+    /// Suspend to RAM
+    SuspendToRam,
+    /// Platform specific sleep type (lower 31 bits)
+    PlatformSpecific(u32),
+}
+
+impl SleepType {
+    /// Returns the `sleep_type` argument
+    #[inline]
+    pub fn code(&self) -> u32 {
+        match *self {
+            SleepType::SuspendToRam => 0,
+            SleepType::PlatformSpecific(sleep_type) => 0x8000_0000 | (sleep_type & 0x7FFF_FFFF),
+        }
+    }
+}
+
+/// Suspends the system
+///
+/// On success the call does not return: the system resumes by starting the calling hart at the
+/// physical address `resume_addr` in S-mode, with `satp` and `sstatus.SIE` cleared, the hart ID in
+/// `a0` and `opaque` in `a1`. All other harts must be stopped before calling this function.
+/// The returned value is always the reason of the failure.
+///
+/// # Safety
+///
+/// `resume_addr` must point to code able to run with the MMU off and restore the state of the
+/// system from `opaque`.
+#[inline]
+pub unsafe fn system_suspend(sleep_type: SleepType, resume_addr: usize, opaque: usize) -> Error {
+    let ret = ecall(
+        EID,
+        SYSTEM_SUSPEND,
+        [sleep_type.code() as usize, resume_addr, opaque, 0, 0, 0],
+    );
+    match ret.into_result() {
+        Ok(_) => Error::Failed,
+        Err(error) => error,
+    }
+}
@@ -0,0 +1,21 @@
+//! Timer extension (EID #0x54494D45 "TIME")
+
+use super::{ecall, split_u64, Error};
+
+/// Extension ID
+pub const EID: usize = 0x54494D45;
+
+pub(crate) const SET_TIMER: usize = 0;
+
+/// Programs the clock for the next event after `stime_value` time
+///
+/// Clears the pending timer interrupt bit. To clear the timer interrupt without scheduling the next
+/// event, either request a timer interrupt infinitely far into the future (`u64::MAX`) or mask the
+/// timer interrupt with `sie.STIE`.
+#[inline]
+pub fn set_timer(stime_value: u64) -> Result<(), Error> {
+    let (value_lo, value_hi) = split_u64(stime_value);
+    unsafe { ecall(EID, SET_TIMER, [value_lo, value_hi, 0, 0, 0, 0]) }
+        .into_result()
+        .map(|_| ())
+}
@@ -0,0 +1,336 @@
+//! Synchronization primitives for multi-hart systems
+//!
+//! `TicketLock` is a fair spinlock: harts acquire it in the order they asked for it. The ticket
+//! counters are updated with atomic memory operations (`amoadd`), so this module is only available
+//! on targets implementing the A extension.
+//!
+//! `SpinMutex` protects data shared between harts. `IrqSpinMutex` additionally masks the
+//! interrupts of the current privilege mode (see `interrupt`) while the lock is held, so that the
+//! data can also be shared with interrupt handlers without deadlocking the hart.
+
+use crate::interrupt;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Ticket spinlock
+pub struct TicketLock {
+    /// Next ticket to hand out
+    next: AtomicUsize,
+    /// Ticket currently holding the lock
+    serving: AtomicUsize,
+}
+
+impl TicketLock {
+    /// Constructs an unlocked lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+        }
+    }
+
+    /// Acquires the lock, spinning until it is available
+    #[inline]
+    pub fn lock(&self) {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        while self.serving.load(Ordering::Acquire) != ticket {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Acquires the lock if it is available, and returns whether it was acquired
+    #[inline]
+    pub fn try_lock(&self) -> bool {
+        let serving = self.serving.load(Ordering::Relaxed);
+        self.next
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+
+    /// Whether the lock is held
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.next.load(Ordering::Relaxed) != self.serving.load(Ordering::Relaxed)
+    }
+
+    /// Releases the lock
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by the caller.
+    #[inline]
+    pub unsafe fn unlock(&self) {
+        // Only the holder of the lock writes `serving`
+        let serving = self.serving.load(Ordering::Relaxed);
+        self.serving
+            .store(serving.wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl Default for TicketLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mutual exclusion primitive protecting `T` with a `TicketLock`
+pub struct SpinMutex<T: ?Sized> {
+    lock: TicketLock,
+    data: UnsafeCell<T>,
+}
+
+// Safety: the lock guarantees exclusive access to `data`
+unsafe impl<T: ?Sized + Send> Sync for SpinMutex<T> {}
+unsafe impl<T: ?Sized + Send> Send for SpinMutex<T> {}
+
+impl<T> SpinMutex<T> {
+    /// Constructs an unlocked mutex
+    #[inline]
+    pub const fn new(data: T) -> Self {
+        Self {
+            lock: TicketLock::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the mutex and returns the protected data
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> SpinMutex<T> {
+    /// Acquires the mutex, spinning until it is available
+    #[inline]
+    pub fn lock(&self) -> SpinMutexGuard<'_, T> {
+        self.lock.lock();
+        SpinMutexGuard {
+            mutex: self,
+            _not_sync: PhantomData,
+        }
+    }
+
+    /// Acquires the mutex if it is available
+    #[inline]
+    pub fn try_lock(&self) -> Option<SpinMutexGuard<'_, T>> {
+        if self.lock.try_lock() {
+            Some(SpinMutexGuard {
+                mutex: self,
+                _not_sync: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Whether the mutex is held
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns a mutable reference to the protected data
+    ///
+    /// No locking is needed: the mutable borrow guarantees exclusive access.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+/// Scoped access to the data of a `SpinMutex`, releasing the mutex when dropped
+///
+/// The guard is `Sync` only if `T` is: sharing it shares a `&T`.
+pub struct SpinMutexGuard<'a, T: ?Sized> {
+    mutex: &'a SpinMutex<T>,
+    _not_sync: PhantomData<*mut ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for SpinMutexGuard<'_, T> {}
+
+impl<'a, T: ?Sized> Deref for SpinMutexGuard<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for SpinMutexGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for SpinMutexGuard<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.mutex.lock.unlock() };
+    }
+}
+
+/// `SpinMutex` masking the interrupts of the current hart while it is held
+pub struct IrqSpinMutex<T: ?Sized> {
+    inner: SpinMutex<T>,
+}
+
+impl<T> IrqSpinMutex<T> {
+    /// Constructs an unlocked mutex
+    #[inline]
+    pub const fn new(data: T) -> Self {
+        Self {
+            inner: SpinMutex::new(data),
+        }
+    }
+
+    /// Consumes the mutex and returns the protected data
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> IrqSpinMutex<T> {
+    /// Disables the interrupts and acquires the mutex, spinning until it is available
+    ///
+    /// The interrupts are restored to their previous state when the guard is dropped.
+    #[inline]
+    pub fn lock(&self) -> IrqSpinMutexGuard<'_, T> {
+        let irq_enabled = unsafe { interrupt::disable_and_save() };
+        self.inner.lock.lock();
+        IrqSpinMutexGuard {
+            mutex: &self.inner,
+            irq_enabled,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Disables the interrupts and acquires the mutex if it is available
+    ///
+    /// The interrupts are left untouched if the mutex is not acquired.
+    #[inline]
+    pub fn try_lock(&self) -> Option<IrqSpinMutexGuard<'_, T>> {
+        let irq_enabled = unsafe { interrupt::disable_and_save() };
+        if self.inner.lock.try_lock() {
+            Some(IrqSpinMutexGuard {
+                mutex: &self.inner,
+                irq_enabled,
+                _not_send: PhantomData,
+            })
+        } else {
+            if irq_enabled {
+                unsafe { interrupt::enable() };
+            }
+            None
+        }
+    }
+
+    /// Whether the mutex is held
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    /// Returns a mutable reference to the protected data
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+/// Scoped access to the data of an `IrqSpinMutex`
+///
+/// Dropping the guard releases the mutex, then re-enables the interrupts if they were enabled
+/// when it was acquired. The guard cannot be sent to another hart, whose interrupts it would
+/// restore.
+pub struct IrqSpinMutexGuard<'a, T: ?Sized> {
+    mutex: &'a SpinMutex<T>,
+    irq_enabled: bool,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl<'a, T: ?Sized> Deref for IrqSpinMutexGuard<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for IrqSpinMutexGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for IrqSpinMutexGuard<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            self.mutex.lock.unlock();
+            if self.irq_enabled {
+                interrupt::enable();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpinMutex, SpinMutexGuard, TicketLock};
+    use core::cell::Cell;
+
+    #[test]
+    fn ticket_lock() {
+        let lock = TicketLock::new();
+        assert!(!lock.is_locked());
+        lock.lock();
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock());
+        unsafe { lock.unlock() };
+        assert!(lock.try_lock());
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    fn spin_mutex() {
+        let mutex = SpinMutex::new(0);
+        {
+            let mut guard = mutex.lock();
+            *guard += 1;
+            assert!(mutex.try_lock().is_none());
+        }
+        *mutex.try_lock().unwrap() += 1;
+        assert!(!mutex.is_locked());
+        assert_eq!(mutex.into_inner(), 2);
+    }
+
+    #[test]
+    fn spin_mutex_guard_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SpinMutexGuard<'static, u32>>();
+
+        // Ambiguous, and rejected, if the type is `Sync`
+        trait AmbiguousIfSync<A> {
+            fn check() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+        impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+        <SpinMutexGuard<'static, Cell<u32>> as AmbiguousIfSync<_>>::check();
+    }
+}
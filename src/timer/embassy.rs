@@ -0,0 +1,150 @@
+//! embassy time driver
+//!
+//! `EmbassyDriver` implements the `Driver` trait of `embassy-time-driver` on top of an `Alarm`,
+//! e.g. a `SupervisorTimer` using `stimecmp` or a `MachineTimer` using the `mtimecmp` register of
+//! an ACLINT MTIMER device. It must be registered as the global time driver with
+//! `embassy_time_driver::time_driver_impl!`, and its `on_interrupt` method called from the
+//! corresponding timer interrupt handler.
+//!
+//! The tick rate of embassy (`embassy_time_driver::TICK_HZ`, selected with the `tick-hz-*`
+//! features of `embassy-time`) does not need to match the frequency of the timer: timestamps are
+//! converted between both with 128-bit arithmetic. Timer deadlines are rounded up, so tasks never
+//! wake up early, and deadlines beyond the range of the timer saturate instead of wrapping around.
+//!
+//! ```ignore
+//! use riscv::timer::embassy::EmbassyDriver;
+//! use riscv::timer::{Backend, SupervisorTimer};
+//!
+//! embassy_time_driver::time_driver_impl!(
+//!     static DRIVER: EmbassyDriver<SupervisorTimer> =
+//!         EmbassyDriver::new(SupervisorTimer::new(Backend::Sstc), 10_000_000)
+//! );
+//!
+//! // Called from the supervisor timer interrupt handler
+//! fn supervisor_timer() {
+//!     DRIVER.on_interrupt();
+//! }
+//! ```
+
+use super::Alarm;
+use crate::interrupt::{self, Mutex};
+use core::cell::RefCell;
+use core::task::Waker;
+use embassy_time_driver::{Driver, TICK_HZ};
+use embassy_time_queue_utils::Queue;
+
+/// Converts `count` ticks of a `from` Hz counter into ticks of a `to` Hz counter, rounding up and
+/// saturating at `u64::MAX`
+#[inline]
+fn convert_ceil(count: u64, from: u64, to: u64) -> u64 {
+    let ticks = (count as u128 * to as u128 + (from as u128 - 1)) / from as u128;
+    if ticks > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        ticks as u64
+    }
+}
+
+/// Converts `count` ticks of a `from` Hz counter into ticks of a `to` Hz counter, rounding down
+#[inline]
+fn convert_floor(count: u64, from: u64, to: u64) -> u64 {
+    ((count as u128 * to as u128) / from as u128) as u64
+}
+
+struct Inner<A> {
+    alarm: A,
+    queue: Queue,
+}
+
+/// embassy time driver backed by an `Alarm`
+///
+/// The timer queue is the one of `embassy-time-queue-utils`: the integrated queue of
+/// `embassy-executor` by default, or a generic queue when one of its `generic-queue-*` features
+/// is enabled.
+pub struct EmbassyDriver<A> {
+    inner: Mutex<RefCell<Inner<A>>>,
+    frequency: u64,
+}
+
+impl<A: Alarm> EmbassyDriver<A> {
+    /// Constructs the driver.
+    /// `frequency` should be the frequency of the `alarm` timer, in Hertz
+    #[inline]
+    pub const fn new(alarm: A, frequency: u64) -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(Inner {
+                alarm,
+                queue: Queue::new(),
+            })),
+            frequency,
+        }
+    }
+
+    /// Handles the timer interrupt
+    ///
+    /// Wakes the tasks whose deadline has passed and re-arms the alarm for the next deadline, or
+    /// disarms it if there is none.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        interrupt::free(|cs| self.process(&mut self.inner.borrow(*cs).borrow_mut()));
+    }
+
+    /// Wakes the expired tasks and arms the alarm for the earliest pending deadline
+    fn process(&self, inner: &mut Inner<A>) {
+        loop {
+            let now = convert_floor(inner.alarm.now(), self.frequency, TICK_HZ);
+            let next = inner.queue.next_expiration(now);
+            if next == u64::MAX {
+                inner.alarm.set_alarm(u64::MAX);
+                return;
+            }
+            let deadline = convert_ceil(next, TICK_HZ, self.frequency);
+            inner.alarm.set_alarm(deadline);
+            // If the deadline passed while arming the alarm, dequeue the expired tasks again
+            if inner.alarm.now() < deadline {
+                return;
+            }
+        }
+    }
+}
+
+impl<A: Alarm + Send + 'static> Driver for EmbassyDriver<A> {
+    #[inline]
+    fn now(&self) -> u64 {
+        let ticks = interrupt::free(|cs| self.inner.borrow(*cs).borrow().alarm.now());
+        convert_floor(ticks, self.frequency, TICK_HZ)
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        interrupt::free(|cs| {
+            let mut inner = self.inner.borrow(*cs).borrow_mut();
+            if inner.queue.schedule_wake(at, waker) {
+                self.process(&mut inner);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_ceil, convert_floor};
+
+    #[test]
+    fn convert() {
+        assert_eq!(convert_floor(10_000_000, 10_000_000, 1_000_000), 1_000_000);
+        assert_eq!(convert_floor(19, 10, 1), 1);
+        assert_eq!(convert_ceil(11, 10, 1), 2);
+        assert_eq!(convert_ceil(10, 10, 1), 1);
+        assert_eq!(convert_ceil(3, 1_000, 32_768), 99);
+        assert_eq!(convert_floor(99, 32_768, 1_000), 3);
+        assert_eq!(convert_ceil(u64::MAX, 1, 2), u64::MAX);
+        assert_eq!(convert_ceil(u64::MAX - 1, 1, 1), u64::MAX - 1);
+    }
+
+    #[test]
+    fn driver_is_sync() {
+        fn assert_driver<D: embassy_time_driver::Driver>() {}
+        assert_driver::<super::EmbassyDriver<crate::timer::SupervisorTimer>>();
+        assert_driver::<super::EmbassyDriver<crate::timer::MachineTimer>>();
+    }
+}
@@ -0,0 +1,173 @@
+//! Timers
+//!
+//! S-mode software programs its timer interrupt either directly through `stimecmp`, when the Sstc
+//! extension is implemented and enabled by M-mode (`menvcfg.STCE`), or through the SBI `set_timer`
+//! call otherwise. `SupervisorTimer` hides the difference; time is always read from the `time` CSR.
+//! M-mode software uses the `mtimecmp` register of an ACLINT MTIMER device through `MachineTimer`.
+//!
+//! Both implement `Alarm`, the single hardware comparator a `queue::TimerQueue` multiplexes many
+//! deadlines onto. The corresponding timer interrupt must still be enabled with `sie::set_stimer`
+//! or `mie::set_mtimer`.
+//!
+//! The `rtic` feature adds monotonics for RTIC (see `rtic`), and the `embassy` feature a time
+//! driver for embassy (see `embassy`).
+
+#[cfg(feature = "embassy")]
+pub mod embassy;
+pub mod queue;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+
+use crate::peripheral::aclint::Mtimer;
+use crate::register::{stimecmp, time};
+use crate::sbi;
+
+/// Timer comparator raising an interrupt once a deadline is reached
+pub trait Alarm {
+    /// Current time, in ticks of the timer
+    fn now(&self) -> u64;
+
+    /// Raises the timer interrupt once `now()` reaches `deadline`
+    ///
+    /// Replaces the previous deadline. `u64::MAX` disarms the alarm and clears a pending interrupt.
+    fn set_alarm(&mut self, deadline: u64);
+}
+
+/// Mechanism used to program the supervisor timer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// Write `stimecmp` directly (Sstc)
+    Sstc,
+    /// Call SBI `set_timer`
+    Sbi,
+}
+
+/// Supervisor timer
+#[derive(Copy, Clone, Debug)]
+pub struct SupervisorTimer {
+    backend: Backend,
+}
+
+impl SupervisorTimer {
+    /// Constructs the timer
+    ///
+    /// `Backend::Sstc` must only be used when M-mode has set `menvcfg.STCE` (see `enable_sstc`),
+    /// otherwise accessing `stimecmp` raises an illegal instruction exception.
+    #[inline]
+    pub const fn new(backend: Backend) -> Self {
+        Self { backend }
+    }
+
+    /// Mechanism used to program the timer
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Current value of the `time` CSR
+    #[inline]
+    pub fn now(&self) -> u64 {
+        time::read64()
+    }
+
+    /// Requests a timer interrupt once `time` reaches `deadline`
+    ///
+    /// Replaces any previously set deadline and clears a pending timer interrupt if `deadline` is
+    /// in the future.
+    #[inline]
+    pub fn set_deadline(&mut self, deadline: u64) -> Result<(), sbi::Error> {
+        match self.backend {
+            Backend::Sstc => {
+                stimecmp::write64(deadline);
+                Ok(())
+            }
+            Backend::Sbi => sbi::time::set_timer(deadline),
+        }
+    }
+
+    /// Requests a timer interrupt `ticks` ticks of `time` from now
+    #[inline]
+    pub fn set_timeout(&mut self, ticks: u64) -> Result<(), sbi::Error> {
+        let deadline = self.now().saturating_add(ticks);
+        self.set_deadline(deadline)
+    }
+
+    /// Cancels the deadline and clears a pending timer interrupt
+    #[inline]
+    pub fn cancel(&mut self) -> Result<(), sbi::Error> {
+        self.set_deadline(u64::MAX)
+    }
+}
+
+impl Alarm for SupervisorTimer {
+    #[inline]
+    fn now(&self) -> u64 {
+        SupervisorTimer::now(self)
+    }
+
+    /// Errors returned by SBI `set_timer` are ignored: the call only fails if the SBI
+    /// implementation does not provide the Timer extension.
+    #[inline]
+    fn set_alarm(&mut self, deadline: u64) {
+        self.set_deadline(deadline).ok();
+    }
+}
+
+/// Machine timer, backed by the `mtimecmp` register of one hart in an ACLINT MTIMER device
+#[derive(Copy, Clone)]
+pub struct MachineTimer {
+    mtimer: &'static Mtimer,
+    hart: usize,
+}
+
+// Safety: `MachineTimer` only writes to the `mtimecmp` register of its own hart, and reads
+// `mtime` with volatile accesses.
+unsafe impl Send for MachineTimer {}
+
+impl MachineTimer {
+    /// Constructs the timer for the `mtimecmp` register of `hart`
+    #[inline]
+    pub const fn new(mtimer: &'static Mtimer, hart: usize) -> Self {
+        Self { mtimer, hart }
+    }
+}
+
+impl Alarm for MachineTimer {
+    #[inline]
+    fn now(&self) -> u64 {
+        self.mtimer.mtime()
+    }
+
+    #[inline]
+    fn set_alarm(&mut self, deadline: u64) {
+        self.mtimer.set_mtimecmp(self.hart, deadline);
+    }
+}
+
+/// Lets S-mode use `stimecmp` and read `time` (M-mode only)
+///
+/// Sets `menvcfg.STCE` (`menvcfgh.STCE` on RV32) and `mcounteren.TM`.
+///
+/// # Safety
+///
+/// Must only be called on harts that implement the Sstc extension. Once enabled, the supervisor
+/// timer interrupt pending bit is driven by `stimecmp` and cannot be written by M-mode any more.
+#[inline]
+pub unsafe fn enable_sstc() {
+    match () {
+        #[cfg(riscv32)]
+        () => {
+            crate::register::menvcfgh::set_stce();
+            crate::register::mcounteren::set_tm();
+        }
+
+        #[cfg(riscv64)]
+        () => {
+            crate::register::menvcfg::set_stce();
+            crate::register::mcounteren::set_tm();
+        }
+
+        #[cfg(not(riscv))]
+        () => unimplemented!(),
+    }
+}
@@ -0,0 +1,282 @@
+//! Software timer queue
+//!
+//! `TimerQueue` keeps up to `N` pending deadlines and arms its `Alarm` for the earliest one. The
+//! timer interrupt handler must call `TimerQueue::on_interrupt`, which wakes the tasks whose
+//! deadline has passed and re-arms the alarm for the next one.
+//!
+//! ```no_run
+//! use riscv::timer::queue::TimerQueue;
+//! use riscv::timer::{Backend, SupervisorTimer};
+//!
+//! static QUEUE: TimerQueue<SupervisorTimer, 8> =
+//!     TimerQueue::new(SupervisorTimer::new(Backend::Sstc), 10_000_000);
+//!
+//! // Called from the supervisor timer interrupt handler
+//! fn supervisor_timer() {
+//!     QUEUE.on_interrupt();
+//! }
+//!
+//! async fn blink() {
+//!     loop {
+//!         // toggle a LED here
+//!         QUEUE.wait_until(QUEUE.now() + 5_000_000).await;
+//!     }
+//! }
+//! ```
+
+use super::Alarm;
+use crate::asm;
+use crate::delay::ticks;
+use crate::interrupt::{self, Mutex};
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use embedded_hal::delay::DelayNs;
+
+struct Slot {
+    deadline: u64,
+    waker: Option<Waker>,
+}
+
+const EMPTY: Option<Slot> = None;
+
+struct Inner<A, const N: usize> {
+    alarm: A,
+    /// Deadline the alarm is currently armed for
+    armed: u64,
+    slots: [Option<Slot>; N],
+}
+
+impl<A: Alarm, const N: usize> Inner<A, N> {
+    /// Stores `deadline` in a free slot and returns its index, or `None` if the queue is full
+    fn insert(&mut self, deadline: u64, waker: Option<Waker>) -> Option<usize> {
+        let index = self.slots.iter().position(|slot| slot.is_none())?;
+        self.slots[index] = Some(Slot { deadline, waker });
+        if deadline < self.armed {
+            self.armed = deadline;
+            self.alarm.set_alarm(deadline);
+        } else if self.armed <= self.alarm.now() {
+            // The interrupt for the armed deadline has not been handled yet (e.g. interrupts are
+            // globally disabled while blocking): re-arm for the earliest pending deadline.
+            self.process();
+        }
+        Some(index)
+    }
+
+    fn register(&mut self, index: usize, waker: &Waker) {
+        if let Some(slot) = &mut self.slots[index] {
+            match &slot.waker {
+                Some(current) if current.will_wake(waker) => {}
+                _ => slot.waker = Some(waker.clone()),
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.slots[index] = None;
+    }
+
+    /// Wakes the expired slots and arms the alarm for the earliest pending deadline
+    fn process(&mut self) {
+        let now = self.alarm.now();
+        let mut next = u64::MAX;
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.deadline <= now {
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            } else if slot.deadline < next {
+                next = slot.deadline;
+            }
+        }
+        self.armed = next;
+        self.alarm.set_alarm(next);
+    }
+}
+
+/// Queue of deadlines sharing a single `Alarm`
+///
+/// Each pending `wait_until` future or blocking delay occupies one of the `N` slots. When all of
+/// them are taken, further delays fall back to polling the timer.
+pub struct TimerQueue<A, const N: usize> {
+    inner: Mutex<RefCell<Inner<A, N>>>,
+    frequency: u64,
+}
+
+impl<A: Alarm, const N: usize> TimerQueue<A, N> {
+    /// Constructs the queue.
+    /// `frequency` should be the frequency of the `alarm` timer, in Hertz
+    #[inline]
+    pub const fn new(alarm: A, frequency: u64) -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(Inner {
+                alarm,
+                armed: u64::MAX,
+                slots: [EMPTY; N],
+            })),
+            frequency,
+        }
+    }
+
+    /// Frequency of the timer, in Hertz
+    #[inline]
+    pub fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    /// Current time, in ticks of the timer
+    #[inline]
+    pub fn now(&self) -> u64 {
+        interrupt::free(|cs| self.inner.borrow(*cs).borrow().alarm.now())
+    }
+
+    /// Handles the timer interrupt
+    ///
+    /// Wakes the tasks whose deadline has passed and re-arms the alarm for the next deadline, or
+    /// disarms it if there is none.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        interrupt::free(|cs| self.inner.borrow(*cs).borrow_mut().process());
+    }
+
+    /// Returns a future that completes once the timer reaches `deadline`
+    #[inline]
+    pub fn wait_until(&self, deadline: u64) -> WaitUntil<'_, A, N> {
+        WaitUntil {
+            queue: self,
+            deadline,
+            slot: None,
+        }
+    }
+
+    /// Blocks until the timer reaches `deadline`, stalling the hart with `WFI` in the meantime
+    ///
+    /// The timer interrupt must be enabled (`sie.STIE` or `mie.MTIE`) for the hart to be woken
+    /// up, although it may be globally disabled.
+    pub fn block_until(&self, deadline: u64) {
+        let slot = interrupt::free(|cs| self.inner.borrow(*cs).borrow_mut().insert(deadline, None));
+        // The deadline is checked with the interrupts masked up to `WFI`, so that the alarm cannot
+        // fire in between and be lost; a pending interrupt still wakes the hart, and is handled
+        // when the critical section ends.
+        loop {
+            let reached = interrupt::free(|cs| {
+                let reached = self.inner.borrow(*cs).borrow().alarm.now() >= deadline;
+                if !reached && slot.is_some() {
+                    unsafe { asm::wfi() };
+                }
+                reached
+            });
+            if reached {
+                break;
+            }
+        }
+        if let Some(slot) = slot {
+            interrupt::free(|cs| self.inner.borrow(*cs).borrow_mut().remove(slot));
+        }
+    }
+
+    /// Returns a delay provider waiting on this queue
+    #[inline]
+    pub fn delay(&self) -> QueueDelay<'_, A, N> {
+        QueueDelay { queue: self }
+    }
+
+    /// Deadline `count` units of `1 / units_second` seconds from now
+    #[inline]
+    fn deadline(&self, count: u64, units_second: u64) -> u64 {
+        self.now()
+            .saturating_add(ticks(count, units_second, self.frequency))
+            .saturating_add(1)
+    }
+}
+
+/// Future returned by `TimerQueue::wait_until`
+pub struct WaitUntil<'a, A: Alarm, const N: usize> {
+    queue: &'a TimerQueue<A, N>,
+    deadline: u64,
+    slot: Option<usize>,
+}
+
+impl<'a, A: Alarm, const N: usize> Future for WaitUntil<'a, A, N> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        interrupt::free(|cs| {
+            let mut inner = this.queue.inner.borrow(*cs).borrow_mut();
+            if inner.alarm.now() >= this.deadline {
+                if let Some(slot) = this.slot.take() {
+                    inner.remove(slot);
+                }
+                return Poll::Ready(());
+            }
+            match this.slot {
+                Some(slot) => inner.register(slot, cx.waker()),
+                None => match inner.insert(this.deadline, Some(cx.waker().clone())) {
+                    Some(slot) => this.slot = Some(slot),
+                    // The queue is full: poll again
+                    None => cx.waker().wake_by_ref(),
+                },
+            }
+            Poll::Pending
+        })
+    }
+}
+
+impl<'a, A: Alarm, const N: usize> Drop for WaitUntil<'a, A, N> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            interrupt::free(|cs| self.queue.inner.borrow(*cs).borrow_mut().remove(slot));
+        }
+    }
+}
+
+/// Delay provider backed by a `TimerQueue`
+///
+/// The blocking delays stall the hart with `WFI` instead of spinning. The asynchronous delays of
+/// embedded-hal-async are implemented when the `embedded-hal-async` feature is enabled.
+pub struct QueueDelay<'a, A, const N: usize> {
+    queue: &'a TimerQueue<A, N>,
+}
+
+impl<'a, A: Alarm, const N: usize> DelayNs for QueueDelay<'a, A, N> {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        self.queue
+            .block_until(self.queue.deadline(ns as u64, 1_000_000_000));
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        self.queue
+            .block_until(self.queue.deadline(us as u64, 1_000_000));
+    }
+
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        self.queue
+            .block_until(self.queue.deadline(ms as u64, 1_000));
+    }
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<'a, A: Alarm, const N: usize> embedded_hal_async::delay::DelayNs for QueueDelay<'a, A, N> {
+    #[inline]
+    async fn delay_ns(&mut self, ns: u32) {
+        let deadline = self.queue.deadline(ns as u64, 1_000_000_000);
+        self.queue.wait_until(deadline).await
+    }
+
+    #[inline]
+    async fn delay_us(&mut self, us: u32) {
+        let deadline = self.queue.deadline(us as u64, 1_000_000);
+        self.queue.wait_until(deadline).await
+    }
+
+    #[inline]
+    async fn delay_ms(&mut self, ms: u32) {
+        let deadline = self.queue.deadline(ms as u64, 1_000);
+        self.queue.wait_until(deadline).await
+    }
+}
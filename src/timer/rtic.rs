@@ -0,0 +1,235 @@
+//! RTIC monotonics
+//!
+//! `stimecmp_monotonic!` and `mtimer_monotonic!` define a type implementing the `Monotonic` trait
+//! of `rtic-time`, counting ticks of the `time` CSR or of the `mtime` register of an ACLINT MTIMER
+//! device. The tick rate given to the macros must be the frequency of that counter; it becomes the
+//! denominator of the `fugit` instants and durations of the monotonic.
+//!
+//! Ticks are 64-bit values read without tearing on RV32, and deadlines are compared with the
+//! wrapping arithmetic of `rtic-time`, so the monotonic never overflows in practice.
+//!
+//! `rtic-time` relies on the `critical-section` crate, which must be provided with an
+//! implementation.
+//!
+//! ```ignore
+//! use fugit::ExtU64;
+//! use rtic_time::Monotonic;
+//!
+//! riscv::stimecmp_monotonic!(Mono, 10_000_000);
+//!
+//! // Called from the supervisor timer interrupt handler
+//! fn supervisor_timer() {
+//!     unsafe { Mono::on_interrupt() };
+//! }
+//!
+//! async fn blink() {
+//!     Mono::start();
+//!     loop {
+//!         // toggle a LED here
+//!         Mono::delay(500.millis()).await;
+//!     }
+//! }
+//! ```
+
+#[doc(hidden)]
+pub mod __private {
+    pub use fugit;
+    pub use rtic_time;
+}
+
+/// Defines a monotonic backed by the `time` CSR and the `stimecmp` register (Sstc)
+///
+/// `$name` is the type to define and `$tick_rate_hz` the frequency of `time`, in Hertz.
+///
+/// `$name::start()` enables the supervisor timer interrupt. M-mode must have enabled Sstc
+/// beforehand (see `timer::enable_sstc`), and `$name::on_interrupt()` must be called from the
+/// supervisor timer interrupt handler.
+#[macro_export]
+macro_rules! stimecmp_monotonic {
+    ($name:ident, $tick_rate_hz:expr) => {
+        /// Monotonic backed by the `time` CSR and the `stimecmp` register
+        pub struct $name;
+
+        impl $name {
+            /// Starts the monotonic and enables the supervisor timer interrupt
+            pub fn start() {
+                $crate::register::stimecmp::write64(u64::MAX);
+                <Self as $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend>::timer_queue()
+                    .initialize(Self);
+                unsafe { $crate::register::sie::set_stimer() };
+            }
+
+            /// Handles the supervisor timer interrupt
+            ///
+            /// # Safety
+            ///
+            /// Must only be called from the supervisor timer interrupt handler.
+            #[inline]
+            pub unsafe fn on_interrupt() {
+                <Self as $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend>::timer_queue()
+                    .on_monotonic_interrupt();
+            }
+        }
+
+        impl $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend for $name {
+            type Ticks = u64;
+
+            #[inline]
+            fn now() -> u64 {
+                $crate::register::time::read64()
+            }
+
+            #[inline]
+            fn set_compare(instant: u64) {
+                $crate::register::stimecmp::write64(instant);
+            }
+
+            // `sip.STIP` reflects the comparison between `time` and `stimecmp`
+            #[inline]
+            fn clear_compare_flag() {}
+
+            #[inline]
+            fn pend_interrupt() {
+                $crate::register::stimecmp::write64(0);
+            }
+
+            #[inline]
+            fn disable_timer() {
+                $crate::register::stimecmp::write64(u64::MAX);
+            }
+
+            fn timer_queue(
+            ) -> &'static $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue<Self> {
+                static QUEUE: $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue<
+                    $name,
+                > = $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue::new();
+                &QUEUE
+            }
+        }
+
+        $crate::__rtic_monotonic_impls!($name, $tick_rate_hz);
+    };
+}
+
+/// Defines a monotonic backed by an ACLINT MTIMER device
+///
+/// `$name` is the type to define, `$tick_rate_hz` the frequency of `mtime`, in Hertz, and
+/// `$mtimer_address` the base address of the MTIMER register block. The monotonic uses the
+/// `mtimecmp` register of the hart it runs on (`mhartid`), so it is only usable in M-mode.
+///
+/// `$name::start()` enables the machine timer interrupt, and `$name::on_interrupt()` must be
+/// called from the machine timer interrupt handler.
+#[macro_export]
+macro_rules! mtimer_monotonic {
+    ($name:ident, $tick_rate_hz:expr, $mtimer_address:expr) => {
+        /// Monotonic backed by an ACLINT MTIMER device
+        pub struct $name;
+
+        impl $name {
+            #[inline]
+            fn mtimer() -> &'static $crate::peripheral::aclint::Mtimer {
+                let address: usize = $mtimer_address;
+                unsafe { &*(address as *const $crate::peripheral::aclint::Mtimer) }
+            }
+
+            #[inline]
+            fn set_mtimecmp(value: u64) {
+                Self::mtimer().set_mtimecmp($crate::register::mhartid::read(), value);
+            }
+
+            /// Starts the monotonic and enables the machine timer interrupt
+            pub fn start() {
+                Self::set_mtimecmp(u64::MAX);
+                <Self as $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend>::timer_queue()
+                    .initialize(Self);
+                unsafe { $crate::register::mie::set_mtimer() };
+            }
+
+            /// Handles the machine timer interrupt
+            ///
+            /// # Safety
+            ///
+            /// Must only be called from the machine timer interrupt handler.
+            #[inline]
+            pub unsafe fn on_interrupt() {
+                <Self as $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend>::timer_queue()
+                    .on_monotonic_interrupt();
+            }
+        }
+
+        impl $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueueBackend for $name {
+            type Ticks = u64;
+
+            #[inline]
+            fn now() -> u64 {
+                Self::mtimer().mtime()
+            }
+
+            #[inline]
+            fn set_compare(instant: u64) {
+                Self::set_mtimecmp(instant);
+            }
+
+            // `mip.MTIP` reflects the comparison between `mtime` and `mtimecmp`
+            #[inline]
+            fn clear_compare_flag() {}
+
+            #[inline]
+            fn pend_interrupt() {
+                Self::set_mtimecmp(0);
+            }
+
+            #[inline]
+            fn disable_timer() {
+                Self::set_mtimecmp(u64::MAX);
+            }
+
+            fn timer_queue(
+            ) -> &'static $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue<Self> {
+                static QUEUE: $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue<
+                    $name,
+                > = $crate::timer::rtic::__private::rtic_time::timer_queue::TimerQueue::new();
+                &QUEUE
+            }
+        }
+
+        $crate::__rtic_monotonic_impls!($name, $tick_rate_hz);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rtic_monotonic_impls {
+    ($name:ident, $tick_rate_hz:expr) => {
+        impl $crate::timer::rtic::__private::rtic_time::monotonic::TimerQueueBasedMonotonic
+            for $name
+        {
+            type Backend = $name;
+            type Instant =
+                $crate::timer::rtic::__private::fugit::Instant<u64, 1, { $tick_rate_hz }>;
+            type Duration =
+                $crate::timer::rtic::__private::fugit::Duration<u64, 1, { $tick_rate_hz }>;
+        }
+
+        $crate::timer::rtic::__private::rtic_time::impl_embedded_hal_delay_fugit!($name);
+        $crate::timer::rtic::__private::rtic_time::impl_embedded_hal_async_delay_fugit!($name);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::__private::rtic_time::Monotonic;
+
+    crate::stimecmp_monotonic!(SstcMono, 10_000_000);
+    crate::mtimer_monotonic!(MtimerMono, 32_768, 0x0200_4000);
+
+    fn assert_monotonic<M: Monotonic>() {}
+
+    #[test]
+    fn monotonics() {
+        assert_monotonic::<SstcMono>();
+        assert_monotonic::<MtimerMono>();
+        let _ = (SstcMono::start, SstcMono::on_interrupt);
+        let _ = (MtimerMono::start, MtimerMono::on_interrupt);
+    }
+}